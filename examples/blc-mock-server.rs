@@ -0,0 +1,25 @@
+//! A local BlogLite stand-in for trying `blc` without a real server.
+//!
+//! ```sh
+//! cargo run --example blc-mock-server -- [ADDR] [PASSWORD]
+//! blc --api http://127.0.0.1:8080 login
+//! ```
+
+#[path = "../tests/support/mock_server.rs"]
+mod mock_server;
+
+use mock_server::MockServer;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let addr = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let password = args.next().unwrap_or_else(|| "password".to_string());
+
+    let server = MockServer::bind(&addr, &password);
+    println!("Mock BlogLite server listening on {}", server.url());
+    println!("Log in with the password {password:?}, the state is lost on exit.");
+
+    loop {
+        std::thread::park();
+    }
+}
@@ -1,158 +1,174 @@
-use std::{fs, path::PathBuf, sync::LazyLock};
+use std::{fs, path::Path};
 
 use crate::{error::Result, Error};
-use reqwest::{
-    multipart::{Form, Part},
-    Client,
-};
-use serde_json::json;
+use chrono::Duration;
+use endpoint::*;
+use reqwest::multipart::{Form, Part};
 
+mod backend;
 mod client;
+pub mod endpoint;
+pub mod fake;
+mod pages;
 pub mod response;
+mod retry;
+mod trace;
 mod util;
 
-use util::return_response;
+pub use backend::BlogBackend;
+pub use client::{BlcClient, BlcClientBuilder, Relogin};
+pub use pages::ArticlePages;
+use response::{Articles, Categories, CategoryItem, Response, Versions};
+pub use retry::RetryPolicy;
+use util::{decode, return_response};
 
 const DEFAULT_FILENAME: &str = "default.md";
 
-static BASE_URL: LazyLock<&str> = LazyLock::new(|| {
-    env!(
-        "BLC_API",
-        "You must set the environment variable $BLC_API before compiling"
-    )
-});
-
-pub async fn login(password: &str) -> Result<String> {
-    let resp = Client::new()
-        .post(format!("{}/authentication", *BASE_URL))
-        .json(&json!({
-            "password": password
-        }))
-        .send()
-        .await?;
-
-    let headers = resp.headers().clone();
-    return_response(resp.json::<response::Response<()>>().await?).and_then(|_| {
-        headers
-            .get("X-Auth-Token")
-            .map(|token| token.to_str().unwrap().into())
-            .ok_or(Error::ServiceError)
-    })
-}
+/// A token issued by [`BlcClient::login`].
+pub struct Session {
+    pub token: String,
 
-pub async fn initializetion_article(uri: &str, category: &str) -> Result<response::Response<()>> {
-    let resp = util::post(
-        &format!("{}/articles", *BASE_URL),
-        &json!({
-            "uri": uri,
-            "category": category,
-        }),
-    )
-    .await?;
-
-    return_response(resp)
+    /// How long the token is valid, if the server says so.
+    pub lifetime: Option<Duration>,
 }
 
-pub async fn delete_article(uri: &str) -> Result<response::Response<()>> {
-    let resp = util::delete(&format!("{}/articles/{}", *BASE_URL, uri)).await?;
-    return_response(resp)
-}
+impl BlogBackend for BlcClient {
+    async fn login(&self, password: &str) -> Result<Session> {
+        let endpoint = Authenticate { password };
+        let url = endpoint::url(&self.endpoint, &endpoint)?;
+        let resp = self
+            .execute(Authenticate::METHOD, url.as_str(), |req| {
+                req.json(&endpoint)
+            })
+            .await?;
+
+        return_response(decode::<()>(&resp)?).and_then(|_| {
+            let token = resp
+                .headers
+                .get("X-Auth-Token")
+                .and_then(|token| token.to_str().ok())
+                .map(str::to_string)
+                .ok_or(Error::ServiceError)?;
+
+            let lifetime = resp
+                .headers
+                .get("X-Auth-Token-Expires-In")
+                .and_then(|secs| secs.to_str().ok()?.parse::<i64>().ok())
+                .map(Duration::seconds);
+
+            Ok(Session { token, lifetime })
+        })
+    }
 
-pub async fn upload_new_version(uri: &str, file_path: &PathBuf) -> Result<response::Response<()>> {
-    let file = fs::read(file_path)?;
+    async fn revoke_token(&self, all_sessions: bool) -> Result<Response<()>> {
+        // 注销时不重新登录
+        let endpoint = RevokeToken { all_sessions };
+        let url = endpoint::url(&self.endpoint, &endpoint)?;
+        let resp = self
+            .send(RevokeToken::METHOD, url.as_str(), &|req| req)
+            .await?;
+        return_response(resp)
+    }
 
-    let file_name = file_path
-        .file_name()
-        .map_or(DEFAULT_FILENAME.to_string(), |name| {
-            name.to_string_lossy().into_owned()
-        });
+    async fn verify_token(&self) -> Result<Response<Articles>> {
+        let endpoint = ArticleList {
+            page: 1,
+            limit: Some(1),
+            category: None,
+            tags: None,
+        };
+        let url = endpoint::url(&self.endpoint, &endpoint)?;
+        let resp = self
+            .send(ArticleList::METHOD, url.as_str(), &|req| req)
+            .await?;
+        return_response(resp)
+    }
 
-    let form = Form::new().part("content", Part::bytes(file).file_name(file_name));
+    async fn initializetion_article(&self, uri: &str, category: &str) -> Result<Response<()>> {
+        self.call(&CreateArticle { uri, category }).await
+    }
 
-    let resp =
-        util::post_with_form(&format!("{}/articles/{}/versions", *BASE_URL, uri), form).await?;
-    return_response(resp)
-}
+    async fn delete_article(&self, uri: &str) -> Result<Response<()>> {
+        self.call(&DeleteArticle { uri }).await
+    }
+
+    async fn upload_new_version(&self, uri: &str, file_path: &Path) -> Result<Response<()>> {
+        let file = fs::read(file_path)?;
 
-pub async fn get_article_list(
-    page: u32,
-    limit: Option<u32>,
-    category: Option<&str>,
-    tags: Option<&str>,
-) -> Result<response::Response<response::Articles>> {
-    let mut query = format!("page={}", page);
+        let file_name = file_path
+            .file_name()
+            .map_or(DEFAULT_FILENAME.to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            });
 
-    if let Some(limit) = limit {
-        query.push_str(&format!("&limit={}", limit));
+        let form = || {
+            Form::new().part(
+                "content",
+                Part::bytes(file.clone()).file_name(file_name.clone()),
+            )
+        };
+
+        self.call_with_form(&UploadVersion { uri }, form).await
     }
 
-    if let Some(category) = category {
-        query.push_str(&format!("&category={}", category));
+    async fn get_article_list(
+        &self,
+        page: u32,
+        limit: Option<u32>,
+        category: Option<&str>,
+        tags: Option<&str>,
+    ) -> Result<Response<Articles>> {
+        self.call(&ArticleList {
+            page,
+            limit,
+            category,
+            tags,
+        })
+        .await
     }
 
-    if let Some(tags) = tags {
-        query.push_str(&format!("&tags={}", tags));
+    async fn delete_article_version(&self, uri: &str, version: &str) -> Result<Response<()>> {
+        self.call(&DeleteVersion { uri, version }).await
     }
 
-    let resp = util::get(&format!("{}/author/articles?{}", *BASE_URL, query)).await?;
-    return_response(resp)
-}
+    async fn get_article_version_list(&self, uri: &str) -> Result<Response<Versions>> {
+        self.call(&VersionList { uri }).await
+    }
 
-pub async fn delete_article_version(uri: &str, version: &str) -> Result<response::Response<()>> {
-    let resp = util::delete(&format!(
-        "{}/articles/{}/versions/{}",
-        *BASE_URL, uri, version
-    ))
-    .await?;
+    async fn set_article_visibility(&self, uri: &str, visibility: bool) -> Result<Response<()>> {
+        self.call(&SetVisibility { uri, visibility }).await
+    }
 
-    return_response(resp)
-}
+    async fn set_article_category(&self, uri: &str, category: &str) -> Result<Response<()>> {
+        self.call(&SetCategory { uri, category }).await
+    }
 
-pub async fn get_article_version_list(uri: &str) -> Result<response::Response<response::Versions>> {
-    let resp = util::get(&format!("{}/articles/{}/versions", *BASE_URL, uri)).await?;
-    return_response(resp)
-}
+    async fn set_article_tags(&self, uri: &str, tags: &str) -> Result<Response<()>> {
+        let tags = tags.split(',').map(|tag| tag.trim()).collect();
+        self.call(&SetTags { uri, tags }).await
+    }
 
-pub async fn set_article_visibility(uri: &str, visibility: bool) -> Result<response::Response<()>> {
-    let resp = util::patch(
-        &format!("{}/articles/{uri}/visibility", *BASE_URL),
-        &json!({
-            "visibility": visibility,
-        }),
-    )
-    .await?;
-    return_response(resp)
-}
+    async fn set_article_version(&self, uri: &str, version: &str) -> Result<Response<()>> {
+        self.call(&SetVersion { uri, version }).await
+    }
 
-pub async fn set_article_category(uri: &str, category: &str) -> Result<response::Response<()>> {
-    let resp = util::patch(
-        &format!("{}/articles/{uri}/categroy", *BASE_URL),
-        &json!({
-            "category": category,
-        }),
-    )
-    .await?;
-    return_response(resp)
-}
+    async fn get_category_list(&self) -> Result<Response<Categories>> {
+        self.call(&CategoryList).await
+    }
 
-pub async fn set_article_tags(uri: &str, tags: &str) -> Result<response::Response<()>> {
-    let resp = util::patch(
-        &format!("{}/articles/{uri}/tags", *BASE_URL),
-        &json!({
-            "tags": tags.split(",").map(|tag| tag.trim()).collect::<Vec<&str>>(),
-        }),
-    )
-    .await?;
-    return_response(resp)
-}
+    async fn get_category(&self, slug: &str) -> Result<Response<CategoryItem>> {
+        self.call(&GetCategory { slug }).await
+    }
+
+    async fn create_category(&self, slug: &str, name: &str) -> Result<Response<()>> {
+        self.call(&CreateCategory { slug, name }).await
+    }
 
-pub async fn set_article_version(uri: &str, version: &str) -> Result<response::Response<()>> {
-    let resp = util::put(
-        &format!("{}/articles/{uri}/tags", *BASE_URL),
-        &json!({
-            "version": version,
-        }),
-    )
-    .await?;
-    return_response(resp)
+    async fn rename_category(&self, slug: &str, name: &str) -> Result<Response<()>> {
+        self.call(&RenameCategory { slug, name }).await
+    }
+
+    async fn delete_category(&self, slug: &str) -> Result<Response<()>> {
+        self.call(&DeleteCategory { slug }).await
+    }
 }
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use super::pages::ArticlePages;
+use super::response::{Articles, Categories, CategoryItem, Response, Versions};
+use super::Session;
+use crate::error::Result;
+
+/// The operations of the BlogLite API used by the commands.
+///
+/// Implemented over HTTP by [`BlcClient`](super::BlcClient) and in memory by
+/// [`MemoryBackend`](super::fake::MemoryBackend), so that the commands can run without a server.
+// 命令只在当前线程上等待, 不需要约束返回的 Future 为 Send
+#[allow(async_fn_in_trait)]
+pub trait BlogBackend {
+    /// Log in with the password and start using the issued token.
+    async fn login(&self, password: &str) -> Result<Session>;
+
+    /// Revoke the token on the server, or with `all_sessions` every token issued for the author.
+    async fn revoke_token(&self, all_sessions: bool) -> Result<Response<()>>;
+
+    /// Check that the server still accepts the token, without logging in again.
+    async fn verify_token(&self) -> Result<Response<Articles>>;
+
+    async fn initializetion_article(&self, uri: &str, category: &str) -> Result<Response<()>>;
+
+    async fn delete_article(&self, uri: &str) -> Result<Response<()>>;
+
+    async fn upload_new_version(&self, uri: &str, file_path: &Path) -> Result<Response<()>>;
+
+    async fn get_article_list(
+        &self,
+        page: u32,
+        limit: Option<u32>,
+        category: Option<&str>,
+        tags: Option<&str>,
+    ) -> Result<Response<Articles>>;
+
+    async fn delete_article_version(&self, uri: &str, version: &str) -> Result<Response<()>>;
+
+    async fn get_article_version_list(&self, uri: &str) -> Result<Response<Versions>>;
+
+    async fn set_article_visibility(&self, uri: &str, visibility: bool) -> Result<Response<()>>;
+
+    async fn set_article_category(&self, uri: &str, category: &str) -> Result<Response<()>>;
+
+    /// `tags` is a comma separated list, e.g. `rust, cli`.
+    async fn set_article_tags(&self, uri: &str, tags: &str) -> Result<Response<()>>;
+
+    async fn set_article_version(&self, uri: &str, version: &str) -> Result<Response<()>>;
+
+    async fn get_category_list(&self) -> Result<Response<Categories>>;
+
+    async fn get_category(&self, slug: &str) -> Result<Response<CategoryItem>>;
+
+    async fn create_category(&self, slug: &str, name: &str) -> Result<Response<()>>;
+
+    async fn rename_category(&self, slug: &str, name: &str) -> Result<Response<()>>;
+
+    async fn delete_category(&self, slug: &str) -> Result<Response<()>>;
+
+    /// Iterate over the pages of the article list, see [`ArticlePages`].
+    fn article_pages<'a>(
+        &'a self,
+        limit: Option<u32>,
+        category: Option<&'a str>,
+        tags: Option<&'a str>,
+    ) -> ArticlePages<'a, Self> {
+        ArticlePages::new(self, limit, category, tags)
+    }
+
+    /// Collect the URIs of every article in a category, following all pages.
+    async fn get_category_article_uris(&self, slug: &str) -> Result<Vec<String>> {
+        let mut uris = Vec::new();
+        let mut pages = self.article_pages(None, Some(slug), None);
+
+        while let Some(articles) = pages.next().await? {
+            uris.extend(articles.items.into_iter().map(|article| article.uri));
+        }
+
+        Ok(uris)
+    }
+}
@@ -0,0 +1,340 @@
+//! The requests of the BlogLite API, one type per endpoint.
+//!
+//! An endpoint knows its method, its path segments and query parameters, and the type of the
+//! `data` in its response. Endpoints sending a JSON body serialize themselves as the body,
+//! path parameters are skipped. Segments and parameters are percent-encoded by [`url`],
+//! so URIs, slugs and tags may contain spaces, `&`, `#`, `/` or CJK characters.
+
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::response::{Articles, Categories, CategoryItem, Versions};
+use crate::error::{Error, Result};
+
+pub trait Endpoint: Serialize {
+    /// Type of the `data` in the response envelope.
+    type Data: DeserializeOwned;
+
+    const METHOD: Method;
+
+    /// Whether the endpoint itself is sent as the JSON body.
+    const BODY: bool = false;
+
+    /// Path below the base URL, one element per segment, unencoded.
+    fn segments(&self) -> Vec<&str>;
+
+    fn query(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+/// The full URL of `endpoint` below `base`, e.g. `https://blog.example.com/api/v1`.
+pub fn url<E: Endpoint>(base: &str, endpoint: &E) -> Result<Url> {
+    let mut url = Url::parse(base).map_err(|_| Error::InvalidEndpoint(base.to_string()))?;
+
+    url.path_segments_mut()
+        .map_err(|_| Error::InvalidEndpoint(base.to_string()))?
+        .pop_if_empty()
+        .extend(endpoint.segments());
+
+    let query = endpoint.query();
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+
+    Ok(url)
+}
+
+#[derive(Serialize)]
+pub struct Authenticate<'a> {
+    pub password: &'a str,
+}
+
+impl Endpoint for Authenticate<'_> {
+    type Data = ();
+    const METHOD: Method = Method::POST;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["authentication"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct RevokeToken {
+    #[serde(skip)]
+    pub all_sessions: bool,
+}
+
+impl Endpoint for RevokeToken {
+    type Data = ();
+    const METHOD: Method = Method::DELETE;
+
+    fn segments(&self) -> Vec<&str> {
+        if self.all_sessions {
+            vec!["authentication", "sessions"]
+        } else {
+            vec!["authentication"]
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ArticleList<'a> {
+    #[serde(skip)]
+    pub page: u32,
+    #[serde(skip)]
+    pub limit: Option<u32>,
+    #[serde(skip)]
+    pub category: Option<&'a str>,
+    #[serde(skip)]
+    pub tags: Option<&'a str>,
+}
+
+impl Endpoint for ArticleList<'_> {
+    type Data = Articles;
+    const METHOD: Method = Method::GET;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["author", "articles"]
+    }
+
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![("page", self.page.to_string())];
+
+        if let Some(limit) = self.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(category) = self.category {
+            query.push(("category", category.to_string()));
+        }
+        if let Some(tags) = self.tags {
+            query.push(("tags", tags.to_string()));
+        }
+
+        query
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreateArticle<'a> {
+    pub uri: &'a str,
+    pub category: &'a str,
+}
+
+impl Endpoint for CreateArticle<'_> {
+    type Data = ();
+    const METHOD: Method = Method::POST;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct DeleteArticle<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+}
+
+impl Endpoint for DeleteArticle<'_> {
+    type Data = ();
+    const METHOD: Method = Method::DELETE;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri]
+    }
+}
+
+/// Sent as a multipart form with the file in the `content` part.
+#[derive(Serialize)]
+pub struct UploadVersion<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+}
+
+impl Endpoint for UploadVersion<'_> {
+    type Data = ();
+    const METHOD: Method = Method::POST;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "versions"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct VersionList<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+}
+
+impl Endpoint for VersionList<'_> {
+    type Data = Versions;
+    const METHOD: Method = Method::GET;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "versions"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct DeleteVersion<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+    #[serde(skip)]
+    pub version: &'a str,
+}
+
+impl Endpoint for DeleteVersion<'_> {
+    type Data = ();
+    const METHOD: Method = Method::DELETE;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "versions", self.version]
+    }
+}
+
+#[derive(Serialize)]
+pub struct SetVisibility<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+    pub visibility: bool,
+}
+
+impl Endpoint for SetVisibility<'_> {
+    type Data = ();
+    const METHOD: Method = Method::PATCH;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "visibility"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct SetCategory<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+    pub category: &'a str,
+}
+
+impl Endpoint for SetCategory<'_> {
+    type Data = ();
+    const METHOD: Method = Method::PATCH;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "category"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct SetTags<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+    pub tags: Vec<&'a str>,
+}
+
+impl Endpoint for SetTags<'_> {
+    type Data = ();
+    const METHOD: Method = Method::PATCH;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "tags"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct SetVersion<'a> {
+    #[serde(skip)]
+    pub uri: &'a str,
+    pub version: &'a str,
+}
+
+impl Endpoint for SetVersion<'_> {
+    type Data = ();
+    const METHOD: Method = Method::PUT;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["articles", self.uri, "version"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct CategoryList;
+
+impl Endpoint for CategoryList {
+    type Data = Categories;
+    const METHOD: Method = Method::GET;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["categories"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct GetCategory<'a> {
+    #[serde(skip)]
+    pub slug: &'a str,
+}
+
+impl Endpoint for GetCategory<'_> {
+    type Data = CategoryItem;
+    const METHOD: Method = Method::GET;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["categories", self.slug]
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreateCategory<'a> {
+    pub slug: &'a str,
+    pub name: &'a str,
+}
+
+impl Endpoint for CreateCategory<'_> {
+    type Data = ();
+    const METHOD: Method = Method::POST;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["categories"]
+    }
+}
+
+#[derive(Serialize)]
+pub struct RenameCategory<'a> {
+    #[serde(skip)]
+    pub slug: &'a str,
+    pub name: &'a str,
+}
+
+impl Endpoint for RenameCategory<'_> {
+    type Data = ();
+    const METHOD: Method = Method::PATCH;
+    const BODY: bool = true;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["categories", self.slug]
+    }
+}
+
+#[derive(Serialize)]
+pub struct DeleteCategory<'a> {
+    #[serde(skip)]
+    pub slug: &'a str,
+}
+
+impl Endpoint for DeleteCategory<'_> {
+    type Data = ();
+    const METHOD: Method = Method::DELETE;
+
+    fn segments(&self) -> Vec<&str> {
+        vec!["categories", self.slug]
+    }
+}
@@ -0,0 +1,469 @@
+//! An in-memory BlogLite server, for exercising the commands without a network.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::response::{
+    ArticleMetadata, Articles, Categories, Category, CategoryItem, Response, Version, Versions,
+};
+use super::util::return_response;
+use super::{BlogBackend, Session};
+use crate::error::Result;
+
+const DEFAULT_PAGE_LIMIT: u32 = 10;
+
+/// Start of the fake clock, every change advances it by one second.
+const EPOCH_MILLIS: i64 = 1_700_000_000_000;
+
+/// A [`BlogBackend`] keeping the whole blog in memory.
+///
+/// It follows the rules of the BlogLite server: requests need a valid token, missing
+/// articles, versions and categories are `RESOURCE_NOT_EXIST`, malformed input is
+/// `INVALID_PARAMETERS` and forbidden state changes are `INVALID_ACTION`.
+///
+/// Handles made by [`MemoryBackend::with_token`] share the blog but send their own token,
+/// like several clients talking to the same server.
+pub struct MemoryBackend {
+    blog: Arc<Mutex<Blog>>,
+    token: Mutex<Option<String>>,
+}
+
+#[derive(Default)]
+struct Blog {
+    password: String,
+    tokens: HashSet<String>,
+    issued: u64,
+    clock: i64,
+
+    // slug -> name
+    categories: BTreeMap<String, String>,
+
+    // 按创建顺序保存, 列表中最新的在前
+    articles: Vec<Article>,
+}
+
+struct Article {
+    uri: String,
+    category: String,
+    tags: Vec<String>,
+    visibility: bool,
+    created_at: i64,
+    current: Option<String>,
+    versions: Vec<StoredVersion>,
+}
+
+struct StoredVersion {
+    version: String,
+    title: String,
+    note: String,
+    created_at: i64,
+}
+
+impl MemoryBackend {
+    /// An empty blog accepting `password`, with this handle already logged in.
+    pub fn new(password: &str) -> Self {
+        let mut blog = Blog {
+            password: password.to_string(),
+            clock: EPOCH_MILLIS,
+            ..Default::default()
+        };
+        let token = blog.issue_token();
+
+        MemoryBackend {
+            blog: Arc::new(Mutex::new(blog)),
+            token: Mutex::new(Some(token)),
+        }
+    }
+
+    /// Another handle to the same blog, sending `token` (or none) with its requests.
+    pub fn with_token(&self, token: Option<&str>) -> Self {
+        MemoryBackend {
+            blog: Arc::clone(&self.blog),
+            token: Mutex::new(token.map(str::to_string)),
+        }
+    }
+
+    /// The token sent with requests.
+    pub fn token(&self) -> Option<String> {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn blog(&self) -> MutexGuard<'_, Blog> {
+        self.blog.lock().unwrap()
+    }
+
+    /// The blog, if the token of this handle is accepted.
+    fn authorized(&self) -> std::result::Result<MutexGuard<'_, Blog>, &'static str> {
+        let blog = self.blog();
+        match self.token() {
+            Some(token) if blog.tokens.contains(&token) => Ok(blog),
+            _ => Err("UNAUTHORISED"),
+        }
+    }
+}
+
+impl Blog {
+    fn issue_token(&mut self) -> String {
+        self.issued += 1;
+        let token = format!("memory-token-{}", self.issued);
+        self.tokens.insert(token.clone());
+        token
+    }
+
+    fn tick(&mut self) -> i64 {
+        self.clock += 1000;
+        self.clock
+    }
+
+    fn article(&self, uri: &str) -> std::result::Result<&Article, &'static str> {
+        self.articles
+            .iter()
+            .find(|article| article.uri == uri)
+            .ok_or("RESOURCE_NOT_EXIST")
+    }
+
+    fn article_mut(&mut self, uri: &str) -> std::result::Result<&mut Article, &'static str> {
+        self.articles
+            .iter_mut()
+            .find(|article| article.uri == uri)
+            .ok_or("RESOURCE_NOT_EXIST")
+    }
+
+    fn category(&self, slug: &str) -> std::result::Result<Category, &'static str> {
+        self.categories
+            .get(slug)
+            .map(|name| Category {
+                slug: slug.to_string(),
+                name: name.clone(),
+            })
+            .ok_or("RESOURCE_NOT_EXIST")
+    }
+
+    fn category_item(&self, slug: &str) -> std::result::Result<CategoryItem, &'static str> {
+        let category = self.category(slug)?;
+        Ok(CategoryItem {
+            article_count: self.article_count(slug),
+            slug: category.slug,
+            name: category.name,
+        })
+    }
+
+    fn article_count(&self, slug: &str) -> u32 {
+        self.articles
+            .iter()
+            .filter(|article| article.category == slug)
+            .count() as u32
+    }
+
+    fn metadata(&self, article: &Article) -> ArticleMetadata {
+        ArticleMetadata {
+            uri: article.uri.clone(),
+            tags: article.tags.clone(),
+            created_at: article
+                .versions
+                .first()
+                .map_or(article.created_at, |version| version.created_at),
+            version: article.current.clone().unwrap_or_default(),
+            visibility: article.visibility,
+            category: self.category(&article.category).unwrap_or(Category {
+                slug: article.category.clone(),
+                name: String::new(),
+            }),
+        }
+    }
+
+    fn article_list(
+        &self,
+        page: u32,
+        limit: Option<u32>,
+        category: Option<&str>,
+        tags: Option<&str>,
+    ) -> std::result::Result<Articles, &'static str> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if page == 0 || limit == 0 {
+            return Err("INVALID_PARAMETERS");
+        }
+
+        let tags = split_tags(tags.unwrap_or_default());
+        let matched = self
+            .articles
+            .iter()
+            .rev()
+            .filter(|article| category.is_none_or(|slug| article.category == slug))
+            .filter(|article| tags.iter().all(|tag| article.tags.contains(tag)))
+            .collect::<Vec<_>>();
+
+        let start = ((page - 1) * limit) as usize;
+        let items = matched
+            .iter()
+            .skip(start)
+            .take(limit as usize)
+            .map(|article| self.metadata(article))
+            .collect();
+
+        Ok(Articles {
+            count: matched.len() as u32,
+            page,
+            items,
+            next: start + (limit as usize) < matched.len(),
+            prev: page > 1,
+        })
+    }
+}
+
+/// Build the response of a request, the same way [`BlcClient`](super::BlcClient) decodes one.
+fn respond<D>(result: std::result::Result<D, &'static str>) -> Result<Response<D>> {
+    match result {
+        Ok(data) => return_response(Response::new("OK", Some(data))),
+        Err(code) => return_response(Response::new(code, None)),
+    }
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The first `# ` heading of a markdown file, like the server uses for the version title.
+fn markdown_title(content: &str) -> Option<&str> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(str::trim)
+}
+
+impl BlogBackend for MemoryBackend {
+    async fn login(&self, password: &str) -> Result<Session> {
+        let mut blog = self.blog();
+        if password != blog.password {
+            return Err(Response::<()>::new("PWD_ERROR", None).into());
+        }
+
+        let token = blog.issue_token();
+        *self.token.lock().unwrap() = Some(token.clone());
+        Ok(Session {
+            token,
+            lifetime: None,
+        })
+    }
+
+    async fn revoke_token(&self, all_sessions: bool) -> Result<Response<()>> {
+        respond(self.authorized().map(|mut blog| {
+            if all_sessions {
+                blog.tokens.clear();
+            } else if let Some(token) = self.token() {
+                blog.tokens.remove(&token);
+            }
+        }))
+    }
+
+    async fn verify_token(&self) -> Result<Response<Articles>> {
+        respond(
+            self.authorized()
+                .and_then(|blog| blog.article_list(1, Some(1), None, None)),
+        )
+    }
+
+    async fn initializetion_article(&self, uri: &str, category: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            if uri.trim().is_empty() || blog.article(uri).is_ok() {
+                return Err("INVALID_PARAMETERS");
+            }
+            blog.category(category)?;
+
+            let created_at = blog.tick();
+            blog.articles.push(Article {
+                uri: uri.to_string(),
+                category: category.to_string(),
+                tags: Vec::new(),
+                visibility: false,
+                created_at,
+                current: None,
+                versions: Vec::new(),
+            });
+            Ok(())
+        }))
+    }
+
+    async fn delete_article(&self, uri: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            blog.article(uri)?;
+            blog.articles.retain(|article| article.uri != uri);
+            Ok(())
+        }))
+    }
+
+    async fn upload_new_version(&self, uri: &str, file_path: &Path) -> Result<Response<()>> {
+        let content = fs::read_to_string(file_path)?;
+        let file_stem = file_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        respond(self.authorized().and_then(|mut blog| {
+            blog.article(uri)?;
+            if content.trim().is_empty() {
+                return Err("INVALID_PARAMETERS");
+            }
+
+            let created_at = blog.tick();
+            let version = format!("{:08x}", created_at / 1000);
+            let article = blog.article_mut(uri)?;
+            article.versions.push(StoredVersion {
+                version: version.clone(),
+                title: markdown_title(&content).unwrap_or(&file_stem).to_string(),
+                note: String::new(),
+                created_at,
+            });
+            article.current = Some(version);
+            Ok(())
+        }))
+    }
+
+    async fn get_article_list(
+        &self,
+        page: u32,
+        limit: Option<u32>,
+        category: Option<&str>,
+        tags: Option<&str>,
+    ) -> Result<Response<Articles>> {
+        respond(
+            self.authorized()
+                .and_then(|blog| blog.article_list(page, limit, category, tags)),
+        )
+    }
+
+    async fn delete_article_version(&self, uri: &str, version: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            let article = blog.article_mut(uri)?;
+            let position = article
+                .versions
+                .iter()
+                .position(|v| v.version == version)
+                .ok_or("RESOURCE_NOT_EXIST")?;
+            if article.current.as_deref() == Some(version) {
+                return Err("INVALID_ACTION");
+            }
+
+            article.versions.remove(position);
+            Ok(())
+        }))
+    }
+
+    async fn get_article_version_list(&self, uri: &str) -> Result<Response<Versions>> {
+        respond(self.authorized().and_then(|blog| {
+            let article = blog.article(uri)?;
+            Ok(Versions {
+                count: article.versions.len() as u32,
+                items: article
+                    .versions
+                    .iter()
+                    .map(|v| Version {
+                        version: v.version.clone(),
+                        note: v.note.clone(),
+                        title: v.title.clone(),
+                        created_at: v.created_at,
+                    })
+                    .collect(),
+            })
+        }))
+    }
+
+    async fn set_article_visibility(&self, uri: &str, visibility: bool) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            let article = blog.article_mut(uri)?;
+            // 没有任何版本的文章不能公开
+            if visibility && article.current.is_none() {
+                return Err("INVALID_ACTION");
+            }
+
+            article.visibility = visibility;
+            Ok(())
+        }))
+    }
+
+    async fn set_article_category(&self, uri: &str, category: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            blog.category(category)?;
+            blog.article_mut(uri)?.category = category.to_string();
+            Ok(())
+        }))
+    }
+
+    async fn set_article_tags(&self, uri: &str, tags: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            blog.article_mut(uri)?.tags = split_tags(tags);
+            Ok(())
+        }))
+    }
+
+    async fn set_article_version(&self, uri: &str, version: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            let article = blog.article_mut(uri)?;
+            if !article.versions.iter().any(|v| v.version == version) {
+                return Err("RESOURCE_NOT_EXIST");
+            }
+
+            article.current = Some(version.to_string());
+            Ok(())
+        }))
+    }
+
+    async fn get_category_list(&self) -> Result<Response<Categories>> {
+        respond(self.authorized().and_then(|blog| {
+            let items = blog
+                .categories
+                .keys()
+                .map(|slug| blog.category_item(slug))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(Categories {
+                count: items.len() as u32,
+                items,
+            })
+        }))
+    }
+
+    async fn get_category(&self, slug: &str) -> Result<Response<CategoryItem>> {
+        respond(self.authorized().and_then(|blog| blog.category_item(slug)))
+    }
+
+    async fn create_category(&self, slug: &str, name: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            if slug.trim().is_empty() || name.trim().is_empty() || blog.category(slug).is_ok() {
+                return Err("INVALID_PARAMETERS");
+            }
+
+            blog.categories.insert(slug.to_string(), name.to_string());
+            Ok(())
+        }))
+    }
+
+    async fn rename_category(&self, slug: &str, name: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            blog.category(slug)?;
+            if name.trim().is_empty() {
+                return Err("INVALID_PARAMETERS");
+            }
+
+            blog.categories.insert(slug.to_string(), name.to_string());
+            Ok(())
+        }))
+    }
+
+    async fn delete_category(&self, slug: &str) -> Result<Response<()>> {
+        respond(self.authorized().and_then(|mut blog| {
+            blog.category(slug)?;
+            if blog.article_count(slug) > 0 {
+                return Err("INVALID_ACTION");
+            }
+
+            blog.categories.remove(slug);
+            Ok(())
+        }))
+    }
+}
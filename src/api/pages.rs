@@ -0,0 +1,148 @@
+use super::response::Articles;
+use super::BlogBackend;
+use crate::error::Result;
+use crate::query::Query;
+
+/// The pages of an article listing, fetched one at a time by following `next`.
+///
+/// ```no_run
+/// # async fn example(client: &blc::api::BlcClient) -> blc::Result<()> {
+/// use blc::api::BlogBackend;
+///
+/// let mut pages = client.article_pages(None, Some("rust"), None).max(100);
+/// while let Some(articles) = pages.next().await? {
+///     for article in &articles.items {
+///         println!("{}", article.uri);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ArticlePages<'a, B: ?Sized> {
+    backend: &'a B,
+    limit: Option<u32>,
+    category: Option<String>,
+    tags: Option<String>,
+
+    /// Checked on every article, see [`ArticlePages::filter`].
+    query: Option<Query>,
+
+    /// The next page to fetch, `None` once the listing is exhausted.
+    page: Option<u32>,
+
+    /// How many more articles may be returned, if capped.
+    remaining: Option<usize>,
+
+    // 因上限而提前停止时, 服务端是否还有更多文章
+    truncated: bool,
+}
+
+impl<'a, B: BlogBackend + ?Sized> ArticlePages<'a, B> {
+    pub fn new(
+        backend: &'a B,
+        limit: Option<u32>,
+        category: Option<&'a str>,
+        tags: Option<&'a str>,
+    ) -> Self {
+        ArticlePages {
+            backend,
+            limit,
+            category: category.map(str::to_string),
+            tags: tags.map(str::to_string),
+            query: None,
+            page: Some(1),
+            remaining: None,
+            truncated: false,
+        }
+    }
+
+    /// Start at another page than the first one.
+    pub fn start(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Stop after `max` articles, cutting the last page short if needed.
+    pub fn max(mut self, max: usize) -> Self {
+        self.remaining = Some(max);
+        self
+    }
+
+    /// Only return the articles matching `query`.
+    ///
+    /// Its category and tags are sent to the server, unless a category was given already.
+    /// Pages may then hold fewer articles than the limit, or none at all.
+    pub fn filter(mut self, query: Query) -> Self {
+        if self.category.is_none() {
+            self.category = query.category().map(str::to_string);
+        }
+
+        let mut tags = self
+            .tags
+            .iter()
+            .flat_map(|tags| tags.split(','))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .chain(query.tags())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        tags.dedup();
+        self.tags = (!tags.is_empty()).then(|| tags.join(","));
+
+        self.query = Some(query);
+        self
+    }
+
+    /// Fetch the next page, or return `None` when there are no more.
+    pub async fn next(&mut self) -> Result<Option<Articles>> {
+        let Some(page) = self.page else {
+            return Ok(None);
+        };
+        if self.remaining == Some(0) {
+            self.page = None;
+            self.truncated = true;
+            return Ok(None);
+        }
+
+        let resp = self
+            .backend
+            .get_article_list(
+                page,
+                self.limit,
+                self.category.as_deref(),
+                self.tags.as_deref(),
+            )
+            .await?;
+        let Some(mut articles) = resp.into_data() else {
+            self.page = None;
+            return Ok(None);
+        };
+
+        // 空页也视为结束, 以免服务端一直返回 next 时无限请求
+        self.page = (articles.next && !articles.items.is_empty()).then_some(page + 1);
+
+        if let Some(query) = &self.query {
+            articles.items.retain(|article| query.matches(article));
+        }
+
+        if let Some(remaining) = &mut self.remaining {
+            if articles.items.len() > *remaining {
+                articles.items.truncate(*remaining);
+                self.page = None;
+                self.truncated = true;
+            }
+            *remaining -= articles.items.len();
+            if *remaining == 0 && self.page.is_some() {
+                self.page = None;
+                self.truncated = true;
+            }
+        }
+
+        Ok(Some(articles))
+    }
+
+    /// Whether the listing was cut short by [`ArticlePages::max`] while the server had more.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
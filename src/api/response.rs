@@ -1,43 +1,138 @@
 use std::fmt::Display;
 
 use chrono::DateTime;
-use serde::Deserialize;
-#[derive(Debug, Deserialize)]
-pub struct Response<D> {
-    code: String,
-    data: Option<D>,
+use serde::{Deserialize, Serialize};
+
+/// The `code` of a response envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum ResponseCode {
+    Ok,
+    Unimplemented,
+    Unauthorised,
+    InternalServiceError,
+    InvalidParameters,
+    InvalidAction,
+    ResourceNotExist,
+    PwdError,
+
+    /// A code this client does not know, kept as sent.
+    Unknown(String),
 }
 
-impl<D> Response<D> {
-    pub fn message(&self) -> String {
-        let result = match self.code.as_str() {
-            "OK" => "OK",
-            "UNIMPLEMENTED" => {
+impl ResponseCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ResponseCode::Ok => "OK",
+            ResponseCode::Unimplemented => "UNIMPLEMENTED",
+            ResponseCode::Unauthorised => "UNAUTHORISED",
+            ResponseCode::InternalServiceError => "INTERNAL_SERVICE_ERROR",
+            ResponseCode::InvalidParameters => "INVALID_PARAMETERS",
+            ResponseCode::InvalidAction => "INVALID_ACTION",
+            ResponseCode::ResourceNotExist => "RESOURCE_NOT_EXIST",
+            ResponseCode::PwdError => "PWD_ERROR",
+            ResponseCode::Unknown(code) => code,
+        }
+    }
+
+    /// What the code means, for when the server does not send a message.
+    pub fn description(&self) -> String {
+        let description = match self {
+            ResponseCode::Ok => "OK",
+            ResponseCode::Unimplemented => {
                 "This feature has not been implemented yet, and the operation is not supported."
             }
-            "UNAUTHORISED" => "The user is not authorized to perform this action.",
-            "INTERNAL_SERVICE_ERROR" => {
+            ResponseCode::Unauthorised => "The user is not authorized to perform this action.",
+            ResponseCode::InternalServiceError => {
                 "An internal system error occurred. Please try again later."
             }
-            "INVALID_PARAMETERS" => "The request contains invalid parameters.",
-            "INVALID_ACTION" => "The action is invalid or unsupported.",
-            "RESOURCE_NOT_EXIST" => "The requested resource does not exist.",
-            "PWD_ERROR" => "Wrong password, please log in again.",
-            _ => "",
+            ResponseCode::InvalidParameters => "The request contains invalid parameters.",
+            ResponseCode::InvalidAction => "The action is invalid or unsupported.",
+            ResponseCode::ResourceNotExist => "The requested resource does not exist.",
+            ResponseCode::PwdError => "Wrong password, please log in again.",
+            ResponseCode::Unknown(code) => {
+                return format!("The server answered with an unknown code {code}.")
+            }
         };
-        result.to_string()
+        description.to_string()
+    }
+}
+
+impl From<&str> for ResponseCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "OK" => ResponseCode::Ok,
+            "UNIMPLEMENTED" => ResponseCode::Unimplemented,
+            "UNAUTHORISED" => ResponseCode::Unauthorised,
+            "INTERNAL_SERVICE_ERROR" => ResponseCode::InternalServiceError,
+            "INVALID_PARAMETERS" => ResponseCode::InvalidParameters,
+            "INVALID_ACTION" => ResponseCode::InvalidAction,
+            "RESOURCE_NOT_EXIST" => ResponseCode::ResourceNotExist,
+            "PWD_ERROR" => ResponseCode::PwdError,
+            code => ResponseCode::Unknown(code.to_string()),
+        }
+    }
+}
+
+impl From<String> for ResponseCode {
+    fn from(code: String) -> Self {
+        ResponseCode::from(code.as_str())
+    }
+}
+
+impl From<ResponseCode> for String {
+    fn from(code: ResponseCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+impl Display for ResponseCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Response<D> {
+    code: ResponseCode,
+    data: Option<D>,
+
+    /// Explanation sent by the server along with the code, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl<D> Response<D> {
+    pub(crate) fn new(code: &str, data: Option<D>) -> Self {
+        Response {
+            code: ResponseCode::from(code),
+            data,
+            message: None,
+        }
+    }
+
+    /// The message of the server, or else what the code means.
+    pub fn message(&self) -> String {
+        match &self.message {
+            Some(message) if !message.is_empty() => message.clone(),
+            _ => self.code.description(),
+        }
     }
 
-    pub fn code(&self) -> String {
-        self.code.clone()
+    pub fn code(&self) -> &ResponseCode {
+        &self.code
     }
 
     pub fn data(&self) -> &Option<D> {
         &self.data
     }
+
+    pub fn into_data(self) -> Option<D> {
+        self.data
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Articles {
     pub count: u32,
     pub page: u32,
@@ -52,9 +147,15 @@ impl Display for Articles {
             writeln!(f, "{}. {}", i + 1, article)?;
         }
 
-        write!(
-            f,
-            "\ncurrent page: {}\nnext page: {}\nprevious page: {}",
+        write!(f, "\n{}", self.page_info())
+    }
+}
+
+impl Articles {
+    /// The position of this page, e.g. for printing below a table of the items.
+    pub fn page_info(&self) -> String {
+        format!(
+            "current page: {}\nnext page: {}\nprevious page: {}",
             self.page,
             if self.next { "Yes" } else { "No" },
             if self.prev { "Yes" } else { "No" }
@@ -62,7 +163,7 @@ impl Display for Articles {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ArticleMetadata {
     pub uri: String,
     pub tags: Vec<String>,
@@ -86,7 +187,7 @@ impl Display for ArticleMetadata {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Category {
     pub slug: String,
     pub name: String,
@@ -98,7 +199,40 @@ impl Display for Category {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Categories {
+    pub count: u32,
+    pub items: Vec<CategoryItem>,
+}
+
+impl Display for Categories {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, item) in self.items.iter().enumerate() {
+            writeln!(f, "{}. {}", i + 1, item)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CategoryItem {
+    pub slug: String,
+    pub name: String,
+    #[serde(rename = "articleCount")]
+    pub article_count: u32,
+}
+
+impl Display for CategoryItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}({}) - {} article(s)",
+            self.name, self.slug, self.article_count
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Versions {
     pub count: u32,
     pub items: Vec<Version>,
@@ -113,7 +247,7 @@ impl Display for Versions {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Version {
     pub version: String,
     pub note: String,
@@ -135,7 +269,7 @@ impl Display for Version {
     }
 }
 
-fn format_detatime_from_timestamp(timestamp: i64) -> String {
+pub fn format_detatime_from_timestamp(timestamp: i64) -> String {
     DateTime::from_timestamp_millis(timestamp)
         .unwrap()
         .format("%Y-%m-%d %H:%M:%S")
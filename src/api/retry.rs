@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+
+/// When and how often [`BlcClient`](super::BlcClient) sends a failed request again.
+///
+/// Requests are retried after connection errors and timeouts, also while reading the body,
+/// and on HTTP 429, 502, 503 and 504. The delay doubles with every retry and is randomized, so that clients do not
+/// retry in lockstep. A `Retry-After` header on 429 and 503 takes precedence.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, 0 disables retrying.
+    pub max_retries: u32,
+
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+
+    /// Upper bound of every delay, including one asked for by `Retry-After`.
+    pub max_delay: Duration,
+
+    /// Also retry POST requests. They may not be idempotent, e.g. an upload may create
+    /// two versions when only the response was lost.
+    pub retry_post: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(30),
+            retry_post: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Send every request once.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a request with `method` may be sent again.
+    pub fn allows(&self, method: &Method) -> bool {
+        match *method {
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::PATCH => true,
+            Method::POST => self.retry_post,
+            _ => false,
+        }
+    }
+
+    /// The randomized delay before retry number `retry`, counting from 0.
+    ///
+    /// It is between half and all of `base_delay * 2^retry`, capped at `max_delay`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let half = delay / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+
+    /// The delay asked for by the `Retry-After` header of a 429 or 503 response.
+    pub fn retry_after(&self, status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+        if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+            return None;
+        }
+
+        let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+        let delay = match value.parse::<u64>() {
+            Ok(secs) => Duration::from_secs(secs),
+            // 也可以是 HTTP 日期, 如 Wed, 21 Oct 2015 07:28:00 GMT
+            Err(_) => {
+                let at = DateTime::parse_from_rfc2822(value)
+                    .ok()?
+                    .with_timezone(&Utc);
+                (at - Utc::now()).to_std().unwrap_or_default()
+            }
+        };
+        Some(delay.min(self.max_delay))
+    }
+
+    /// How long to wait before sending the request again after `result`, or `None` to give up.
+    ///
+    /// `retry` is the number of retries made so far.
+    pub(crate) fn delay(
+        &self,
+        method: &Method,
+        retry: u32,
+        result: &reqwest::Result<reqwest::Response>,
+    ) -> Option<Duration> {
+        if !self.may_retry(method, retry) {
+            return None;
+        }
+
+        match result {
+            Ok(resp) if retryable_status(resp.status()) => Some(
+                self.retry_after(resp.status(), resp.headers())
+                    .unwrap_or_else(|| self.backoff(retry)),
+            ),
+            Err(e) if retryable_error(e) => Some(self.backoff(retry)),
+            _ => None,
+        }
+    }
+
+    /// How long to wait before sending the request again after reading its body failed with `e`.
+    pub(crate) fn body_delay(
+        &self,
+        method: &Method,
+        retry: u32,
+        e: &reqwest::Error,
+    ) -> Option<Duration> {
+        (self.may_retry(method, retry) && retryable_error(e)).then(|| self.backoff(retry))
+    }
+
+    fn may_retry(&self, method: &Method, retry: u32) -> bool {
+        retry < self.max_retries && self.allows(method)
+    }
+}
+
+/// Responses that are likely to succeed when the request is sent again.
+fn retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Errors of the connection rather than of the request itself.
+fn retryable_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect()
+}
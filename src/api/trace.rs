@@ -0,0 +1,138 @@
+//! Request logging for `--verbose`.
+//!
+//! `--verbose` logs the method, URL, status and duration of every request, and the raw body
+//! of a response that is not the expected JSON envelope. Given twice, it adds the headers and
+//! bodies. Tokens and passwords are replaced by `***` before anything is logged.
+
+use std::fmt::Display;
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Request, Response};
+use serde_json::Value;
+
+const REDACTED: &str = "***";
+
+/// Headers carrying a token, in requests or in the response to a login.
+const SECRET_HEADERS: &[&str] = &["authorization", "x-auth-token", "cookie", "set-cookie"];
+
+/// Fields of JSON bodies carrying a password.
+const SECRET_FIELDS: &[&str] = &["password"];
+
+/// Bodies are cut off after this many bytes, e.g. for a large article.
+const MAX_BODY: usize = 4096;
+
+pub(crate) fn request(req: &Request) {
+    if !tracing::enabled!(tracing::Level::DEBUG) {
+        return;
+    }
+
+    tracing::debug!("> {} {}", req.method(), req.url());
+    headers(">", req.headers());
+    match req.body().map(|body| body.as_bytes()) {
+        Some(Some(bytes)) => tracing::debug!("> {}", redact_body(bytes)),
+        // multipart 表单是流式的, 无法在发送前读取
+        Some(None) => tracing::debug!("> <streamed body>"),
+        None => {}
+    }
+}
+
+pub(crate) fn response(
+    method: &Method,
+    url: &str,
+    result: &reqwest::Result<Response>,
+    elapsed: Duration,
+) {
+    match result {
+        Ok(resp) => {
+            tracing::info!(
+                "{method} {url} -> {} ({} ms)",
+                resp.status(),
+                elapsed.as_millis()
+            );
+            headers("<", resp.headers());
+        }
+        Err(e) => tracing::info!(
+            "{method} {url} failed after {} ms: {e}",
+            elapsed.as_millis()
+        ),
+    }
+}
+
+pub(crate) fn body_failed(method: &Method, url: &str, e: &reqwest::Error, elapsed: Duration) {
+    tracing::info!(
+        "{method} {url} failed reading the body after {} ms: {e}",
+        elapsed.as_millis()
+    );
+}
+
+pub(crate) fn retry(delay: Duration, retry: u32, max_retries: u32) {
+    tracing::info!(
+        "retrying in {} ms ({}/{max_retries})",
+        delay.as_millis(),
+        retry + 1
+    );
+}
+
+pub(crate) fn body(bytes: &[u8]) {
+    tracing::debug!("< {}", redact_body(bytes));
+}
+
+/// Log a body that is not the expected envelope, with the reason.
+pub(crate) fn unexpected_body(bytes: &[u8], reason: &dyn Display) {
+    tracing::warn!(
+        "unexpected response ({reason}), body: {}",
+        redact_body(bytes)
+    );
+}
+
+fn headers(direction: &str, headers: &HeaderMap) {
+    for (name, value) in headers {
+        let value = if SECRET_HEADERS.contains(&name.as_str()) {
+            // 保留认证方式, 如 Bearer
+            match value.to_str().ok().and_then(|v| v.split_once(' ')) {
+                Some((scheme, _)) => format!("{scheme} {REDACTED}"),
+                None => REDACTED.to_string(),
+            }
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        tracing::debug!("{direction} {name}: {value}");
+    }
+}
+
+/// The body as text, with the password fields of a JSON body replaced.
+fn redact_body(bytes: &[u8]) -> String {
+    let text = match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut value) => {
+            redact_fields(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    };
+
+    if text.len() <= MAX_BODY {
+        return text;
+    }
+    let mut end = MAX_BODY;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &text[..end], text.len())
+}
+
+fn redact_fields(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_fields(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_fields),
+        _ => {}
+    }
+}
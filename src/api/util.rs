@@ -1,68 +1,237 @@
-use super::response;
-use crate::api::client::HTTP_CLIENT;
+use super::endpoint::{self, Endpoint};
+use super::response::{self, ResponseCode};
+use super::{trace, BlcClient, BlogBackend};
 use crate::error::{self, Error, Result};
-use reqwest::{multipart::Form, Method};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{multipart::Form, Method, StatusCode};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 
-pub(crate) fn return_response<T>(resp: response::Response<T>) -> Result<response::Response<T>> {
-    match resp.code().as_str() {
-        "OK" => Ok(resp),
-        _ => Err(Error::from(resp)),
-    }
+/// A response read in full by [`BlcClient::execute`].
+pub(crate) struct Received {
+    pub status: StatusCode,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
 }
 
-pub(crate) async fn post<T, R>(url: &str, json: &T) -> error::Result<R>
-where
-    T: Serialize,
-    R: DeserializeOwned,
-{
-    let resp = request(Method::POST, url, |req| req.json(json)).await?;
-    Ok(resp)
-}
+/// Decode the JSON envelope of `resp`, logging the raw body if that fails.
+///
+/// Bodies that are not JSON, e.g. the HTML error page of a proxy, are never decoded.
+/// An HTTP error status is reported instead, or the unexpected content type.
+pub(crate) fn decode<D: DeserializeOwned>(resp: &Received) -> Result<response::Response<D>> {
+    let content_type = resp
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        });
 
-pub(crate) async fn put<T, R>(url: &str, json: &T) -> error::Result<R>
-where
-    T: Serialize,
-    R: DeserializeOwned,
-{
-    let resp = request(Method::PUT, url, |req| req.json(json)).await?;
-    Ok(resp)
-}
+    // 缺少 Content-Type 时仍尝试解析
+    if let Some(content_type) = content_type.filter(|t| !is_json(t)) {
+        trace::unexpected_body(&resp.body, &content_type);
+        return Err(
+            status_error(resp.status, &resp.url).unwrap_or(Error::UnexpectedContentType {
+                content_type,
+                url: resp.url.clone(),
+            }),
+        );
+    }
 
-pub(crate) async fn patch<T, R>(url: &str, json: &T) -> error::Result<R>
-where
-    T: Serialize,
-    R: DeserializeOwned,
-{
-    let resp = request(Method::PATCH, url, |req| req.json(json)).await?;
-    Ok(resp)
+    serde_json::from_slice(&resp.body).map_err(|e| {
+        trace::unexpected_body(&resp.body, &e);
+        status_error(resp.status, &resp.url).unwrap_or(Error::InvalidResponse(e))
+    })
 }
 
-pub(crate) async fn post_with_form<R: DeserializeOwned>(url: &str, form: Form) -> error::Result<R> {
-    let resp = request(Method::POST, url, |req| req.multipart(form)).await?;
-    Ok(resp)
+fn is_json(content_type: &str) -> bool {
+    content_type == "application/json" || content_type.ends_with("+json")
 }
 
-pub(crate) async fn delete<R: DeserializeOwned>(url: &str) -> error::Result<R> {
-    let resp = request(Method::DELETE, url, |req| req).await?;
-    Ok(resp)
+/// The error for a response without a usable envelope, unless its status is a success.
+fn status_error(status: StatusCode, url: &str) -> Option<Error> {
+    let error = match status {
+        _ if status.is_success() => return None,
+        StatusCode::NOT_FOUND => Error::EndpointNotFound(url.to_string()),
+        StatusCode::PAYLOAD_TOO_LARGE => Error::PayloadTooLarge,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => {
+            Error::GatewayError(status)
+        }
+        _ => Error::HttpStatus {
+            status,
+            url: url.to_string(),
+        },
+    };
+    Some(error)
 }
 
-pub(crate) async fn get<R: DeserializeOwned>(url: &str) -> error::Result<R> {
-    let resp = request(Method::GET, url, |req| req).await?;
-    Ok(resp)
+pub(crate) fn return_response<T>(resp: response::Response<T>) -> Result<response::Response<T>> {
+    match resp.code() {
+        ResponseCode::Ok => Ok(resp),
+        _ => Err(Error::from(resp)),
+    }
 }
 
-pub(crate) async fn request<R, F>(method: Method, url: &str, build_req: F) -> error::Result<R>
-where
-    R: DeserializeOwned,
-    F: FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
-{
-    let resp = build_req(HTTP_CLIENT.request(method, url))
-        .send()
-        .await?
-        .json::<R>()
-        .await?;
-    Ok(resp)
+impl BlcClient {
+    /// Send `endpoint`, with itself as the JSON body if it has one.
+    pub(crate) async fn call<E: Endpoint>(
+        &self,
+        endpoint: &E,
+    ) -> Result<response::Response<E::Data>> {
+        let url = endpoint::url(&self.endpoint, endpoint)?;
+        let resp = self
+            .request(E::METHOD, url.as_str(), |req| {
+                if E::BODY {
+                    req.json(endpoint)
+                } else {
+                    req
+                }
+            })
+            .await?;
+        return_response(resp)
+    }
+
+    /// Send `endpoint` with a multipart body.
+    ///
+    /// `form` builds the body, it is called again when the request is retried.
+    pub(crate) async fn call_with_form<E, F>(
+        &self,
+        endpoint: &E,
+        form: F,
+    ) -> Result<response::Response<E::Data>>
+    where
+        E: Endpoint,
+        F: Fn() -> Form,
+    {
+        let url = endpoint::url(&self.endpoint, endpoint)?;
+        let resp = self
+            .request(E::METHOD, url.as_str(), |req| req.multipart(form()))
+            .await?;
+        return_response(resp)
+    }
+
+    /// Send an authenticated request.
+    ///
+    /// When the server rejects the token and a [`Relogin`](super::Relogin) hook is set,
+    /// the client logs in again and sends the request once more with the new token.
+    pub(crate) async fn request<D, F>(
+        &self,
+        method: Method,
+        url: &str,
+        build_req: F,
+    ) -> error::Result<response::Response<D>>
+    where
+        D: DeserializeOwned,
+        F: Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        let resp = self.send(method.clone(), url, &build_req).await?;
+
+        if *resp.code() == ResponseCode::Unauthorised && self.try_relogin().await? {
+            return self.send(method, url, &build_req).await;
+        }
+
+        Ok(resp)
+    }
+
+    /// Send an authenticated request once, without the re-login of [`BlcClient::request`].
+    pub(crate) async fn send<D, F>(
+        &self,
+        method: Method,
+        url: &str,
+        build_req: &F,
+    ) -> error::Result<response::Response<D>>
+    where
+        D: DeserializeOwned,
+        F: Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        let token = self.token().ok_or(Error::UnAuth)?;
+        let resp = self
+            .execute(method, url, |req| build_req(req.bearer_auth(&token)))
+            .await?;
+        decode(&resp)
+    }
+
+    /// Send a request and read its body, retrying both according to the
+    /// [`RetryPolicy`](super::RetryPolicy).
+    ///
+    /// `build_req` is called again for every attempt. The last response is returned as is,
+    /// even when its status asked for a retry.
+    pub(crate) async fn execute<F>(
+        &self,
+        method: Method,
+        url: &str,
+        build_req: F,
+    ) -> Result<Received>
+    where
+        F: Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        let mut retry = 0;
+        loop {
+            let req = build_req(self.http.request(method.clone(), url)).build()?;
+            trace::request(&req);
+
+            let started = Instant::now();
+            let result = self.http.execute(req).await;
+            trace::response(&method, url, &result, started.elapsed());
+
+            let delay = match self.retry.delay(&method, retry, &result) {
+                Some(delay) => delay,
+                None => {
+                    let resp = result?;
+                    let status = resp.status();
+                    let headers = resp.headers().clone();
+                    let resp_url = resp.url().to_string();
+                    // 读取响应体时也可能超时, 与发送请求一样重试
+                    match resp.bytes().await {
+                        Ok(body) => {
+                            trace::body(&body);
+                            return Ok(Received {
+                                status,
+                                url: resp_url,
+                                headers,
+                                body: body.into(),
+                            });
+                        }
+                        Err(e) => {
+                            trace::body_failed(&method, url, &e, started.elapsed());
+                            match self.retry.body_delay(&method, retry, &e) {
+                                Some(delay) => delay,
+                                None => return Err(e.into()),
+                            }
+                        }
+                    }
+                }
+            };
+
+            trace::retry(delay, retry, self.retry.max_retries);
+            tokio::time::sleep(delay).await;
+            retry += 1;
+        }
+    }
+
+    /// Log in again through the [`Relogin`](super::Relogin) hook, at most once per client.
+    async fn try_relogin(&self) -> Result<bool> {
+        let Some(relogin) = self.relogin() else {
+            return Ok(false);
+        };
+        if self.relogin_attempted.swap(true, Ordering::SeqCst) {
+            return Ok(false);
+        }
+        let Some(password) = relogin.password() else {
+            return Ok(false);
+        };
+
+        let session = self.login(&password).await?;
+        relogin.renewed(&session)?;
+        self.set_token(session.token);
+
+        Ok(true)
+    }
 }
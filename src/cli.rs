@@ -1,10 +1,38 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::output::OutputFormat;
+
 #[derive(Parser, Debug)]
 #[command(version, author, about, long_about = None)]
 /// A CLI tool for managing articles in your blog platform.
 pub struct Cli {
+    /// Base URL of the BlogLite API, overrides the endpoint saved at login.
+    #[arg(long, global = true, env = "BLC_API")]
+    pub api: Option<String>,
+
+    /// Named profile to use, each with its own endpoint and login state.
+    #[arg(long, global = true, env = "BLC_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Output format, defaults to `output` from the configuration.
+    #[arg(short, long, global = true, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// Log requests to standard error, given twice also logs headers and bodies.
+    ///
+    /// Tokens and passwords are never logged.
+    #[arg(long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Short for `--verbose`, before the subcommand, e.g. `blc -vv list`.
+    // 不能是全局的, 否则与 `set -v <VERSION>` 冲突
+    #[arg(short = 'v', action = ArgAction::Count)]
+    pub verbose_short: u8,
+
+    #[command(flatten)]
+    pub network: NetworkArgs,
+
     #[command(subcommand)]
     pub action: Action,
 }
@@ -12,16 +40,41 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Action {
     /// All actions must be executed after logging in.
-    Login,
+    ///
+    /// The password is prompted for, unless `--password-stdin` is given or `$BLC_PASSWORD` is set.
+    /// Alternatively `$BLC_TOKEN` supplies a token directly without logging in or touching `~/.blc`.
+    Login {
+        /// Read the password from the first line of standard input.
+        #[arg(long)]
+        password_stdin: bool,
+    },
+
+    /// Show the current login state and check that the server still accepts it.
+    #[command(visible_alias = "whoami")]
+    Status,
+
+    /// Clear local login status of the current profile.
+    ///
+    /// The token is revoked on the server first, unless `--local-only` is given.
+    Logout {
+        /// Clear the login status of every profile.
+        #[arg(long)]
+        all: bool,
+
+        /// Only clear the local login status, leaving the token valid on the server.
+        #[arg(long, conflicts_with = "all_sessions")]
+        local_only: bool,
 
-    /// Clear local login status.
-    Logout,
+        /// Invalidate every token issued for the author, not just the current one.
+        #[arg(long)]
+        all_sessions: bool,
+    },
 
     /// Initialize a new article with a category and URI.
     Init {
-        /// The category of the article.
+        /// The category of the article, defaults to `default_category` from the configuration.
         #[arg(short, long)]
-        category: String,
+        category: Option<String>,
 
         /// Custom article URI.
         uri: String,
@@ -67,7 +120,7 @@ pub enum Action {
         #[arg(short, long)]
         page: Option<u32>,
 
-        /// The number of articles to display per page.
+        /// The number of articles to display per page, defaults to `page_limit` from the configuration.
         #[arg(short, long)]
         limit: Option<u32>,
 
@@ -78,13 +131,270 @@ pub enum Action {
         /// Filter by tags.
         #[arg(long)]
         tags: Option<String>,
+
+        /// Only list articles matching an expression, e.g.
+        /// `category=rust and tag:async and not visible and created>2024-01-01`.
+        ///
+        /// Follows all pages like `--all`.
+        #[arg(long = "where", value_name = "EXPR", conflicts_with = "page")]
+        filter: Option<String>,
+
+        /// Follow all pages, printing the articles as they arrive.
+        ///
+        /// With `--sort`, `--reverse` or `--output json|yaml` all pages are fetched first.
+        #[arg(long, conflicts_with = "page")]
+        all: bool,
+
+        /// Stop after N articles, following pages as with `--all`.
+        #[arg(long, value_name = "N", conflicts_with = "page")]
+        max: Option<usize>,
+
+        #[command(flatten)]
+        table: TableArgs,
     },
 
     /// Show all versions of a specific article.
     Versions {
         /// The URI of the article.
         uri: String,
+
+        #[command(flatten)]
+        table: TableArgs,
     },
+
+    /// Search the URIs, tags, titles and version notes of all articles.
+    ///
+    /// An article matches when it contains every word, ignoring case.
+    Search {
+        /// The words to look for.
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Only search articles matching an expression, see `list --where`.
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Option<String>,
+
+        /// Show at most this many results.
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Fetch all version lists again instead of using the local cache.
+        #[arg(long)]
+        refresh: bool,
+    },
+
+    /// Manage article categories.
+    Category {
+        #[command(subcommand)]
+        action: CategoryAction,
+    },
+
+    /// Inspect and edit the configuration files.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Manage named profiles, e.g. one per blog.
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileAction {
+    /// Add a profile or change its endpoint.
+    Add {
+        /// The name of the profile.
+        name: String,
+
+        /// Base URL of the BlogLite API for this profile.
+        endpoint: String,
+    },
+
+    /// Make a profile the default one.
+    Use {
+        /// The name of the profile.
+        name: String,
+    },
+
+    /// List all known profiles.
+    List,
+
+    /// Remove a profile together with its login state.
+    Remove {
+        /// The name of the profile.
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the effective value of a key.
+    Get {
+        /// The key to look up, e.g. `page_limit` or `aliases.ls`.
+        key: String,
+
+        /// Also print where the value comes from.
+        #[arg(long)]
+        show_origin: bool,
+    },
+
+    /// Write a key to the user configuration file.
+    Set {
+        /// The key to set, e.g. `page_limit` or `aliases.ls`.
+        key: String,
+
+        /// The new value.
+        value: String,
+
+        /// Write to the nearest project `blc.toml` instead of the user configuration.
+        #[arg(long)]
+        project: bool,
+    },
+
+    /// Print every effective key and value.
+    List {
+        /// Also print where each value comes from.
+        #[arg(long)]
+        show_origin: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CategoryAction {
+    /// List all categories with their article counts.
+    List {
+        /// Print each category with a template, or a template named in the configuration.
+        #[arg(long, value_name = "TEMPLATE")]
+        format: Option<String>,
+    },
+
+    /// Show a category and the articles in it.
+    Show {
+        /// The slug of the category.
+        slug: String,
+    },
+
+    /// Create a new category.
+    Create {
+        /// The slug of the category, used in URIs and filters.
+        slug: String,
+
+        /// The display name of the category.
+        #[arg(short, long)]
+        name: String,
+    },
+
+    /// Change the display name of a category.
+    Rename {
+        /// The slug of the category.
+        slug: String,
+
+        /// The new display name.
+        name: String,
+    },
+
+    /// Delete a category. Non-empty categories are refused unless `--reassign` is given.
+    Delete {
+        /// The slug of the category to delete.
+        slug: String,
+
+        /// Move the articles of this category to another category before deleting it.
+        #[arg(long, value_name = "SLUG")]
+        reassign: Option<String>,
+    },
+}
+
+/// Timeouts, retries, proxy and TLS settings of the requests to the server,
+/// overriding the configuration.
+#[derive(Args, Debug, Default)]
+pub struct NetworkArgs {
+    /// Total timeout of each attempt of a request in seconds.
+    #[arg(long, global = true, value_name = "SECS", help_heading = "Network")]
+    pub timeout: Option<u64>,
+
+    /// Timeout of establishing a connection in seconds.
+    #[arg(long, global = true, value_name = "SECS", help_heading = "Network")]
+    pub connect_timeout: Option<u64>,
+
+    /// Timeout of each read from the connection in seconds.
+    #[arg(long, global = true, value_name = "SECS", help_heading = "Network")]
+    pub read_timeout: Option<u64>,
+
+    /// How often a failed request is retried, 0 disables retrying [default: 2].
+    #[arg(long, global = true, value_name = "N", help_heading = "Network")]
+    pub retries: Option<u32>,
+
+    /// Delay before the first retry, doubled for every further retry [default: 250].
+    #[arg(long, global = true, value_name = "MS", help_heading = "Network")]
+    pub retry_delay_ms: Option<u64>,
+
+    /// Upper bound of the delay between retries, also for `Retry-After` [default: 30000].
+    #[arg(long, global = true, value_name = "MS", help_heading = "Network")]
+    pub retry_max_delay_ms: Option<u64>,
+
+    /// Also retry POST requests, e.g. uploads, which may then be applied twice.
+    #[arg(long, global = true, help_heading = "Network")]
+    pub retry_post: bool,
+
+    /// Send all requests through this proxy, instead of the one from `$HTTPS_PROXY`.
+    #[arg(long, global = true, value_name = "URL", help_heading = "Network")]
+    pub proxy: Option<String>,
+
+    /// Also trust the root certificates in this PEM file, e.g. of an internal CA.
+    #[arg(long, global = true, value_name = "PATH", help_heading = "Network")]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Client certificate chain for mutual TLS, as a PEM file. Requires `--client-key`.
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        requires = "client_key",
+        help_heading = "Network"
+    )]
+    pub client_cert: Option<PathBuf>,
+
+    /// PKCS #8 private key of the client certificate, as a PEM file.
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        requires = "client_cert",
+        help_heading = "Network"
+    )]
+    pub client_key: Option<PathBuf>,
+
+    /// Do not verify the TLS certificate of the server. Only for testing.
+    #[arg(long, global = true, help_heading = "Network")]
+    pub insecure: bool,
+}
+
+/// Layout of the table printed by `list` and `versions`.
+#[derive(Args, Debug, Default)]
+pub struct TableArgs {
+    /// Columns to show, comma separated, e.g. `uri,category,tags,version,created`.
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Option<Vec<String>>,
+
+    /// Sort the rows by a column, e.g. `created`, `uri` or `category`.
+    #[arg(long, value_name = "COLUMN")]
+    pub sort: Option<String>,
+
+    /// Reverse the order of the rows.
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Leave out the header row.
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// Print each row with a template instead, e.g. `{uri}\t{tags|join(",")}`,
+    /// or the name of a template from the configuration.
+    #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["columns", "no_header"])]
+    pub format: Option<String>,
 }
 
 #[derive(Debug, Args)]
@@ -0,0 +1,454 @@
+//! The article and category commands, independent of how the blog is reached.
+
+use std::fmt::Display;
+use std::io::Write;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::api::response::{Articles, CategoryItem};
+use crate::api::{ArticlePages, BlogBackend};
+use crate::config;
+use crate::error::{Error, Result};
+use crate::output::{Described, OutputFormat, Printer};
+use crate::query::Query;
+use crate::search::{self, Search, VersionCache};
+use crate::settings::Settings;
+use crate::table::{self, ARTICLE_COLUMNS, VERSION_COLUMNS};
+use crate::template::Template;
+use crate::{Action, CategoryAction, TableArgs};
+
+/// Run a command against the blog, writing its data to `printer`.
+///
+/// Returns the message of the finished command, if it has one. Commands that only touch
+/// local state, like `login` or `config`, are handled by the binary and do nothing here.
+/// `profile` selects the cache of `search`.
+pub async fn run<B: BlogBackend, W: Write>(
+    backend: &B,
+    action: &Action,
+    settings: &Settings,
+    profile: &str,
+    printer: &mut Printer<W>,
+) -> Result<Option<String>> {
+    match action {
+        Action::Init { uri, category } => {
+            let category = category
+                .as_deref()
+                .or(settings.default_category.as_deref())
+                .ok_or(Error::MissingCategory)?;
+            backend.initializetion_article(uri, category).await?;
+            Ok(Some(format!("Article Initialization, uri: {}", uri.bold())))
+        }
+
+        Action::Delete { uri } => {
+            backend.delete_article(uri).await?;
+            forget_versions(settings, profile, uri)?;
+            Ok(Some(format!("Article deleted, uri: {}", uri.bold())))
+        }
+
+        Action::Upload { uri, path } => {
+            backend.upload_new_version(uri, path).await?;
+            forget_versions(settings, profile, uri)?;
+            Ok(Some(format!("Article uploaded, uri: {}", uri.bold())))
+        }
+
+        Action::List {
+            page,
+            limit,
+            category,
+            tags,
+            filter,
+            all,
+            max,
+            table,
+        } => {
+            if *all || max.is_some() || filter.is_some() {
+                let mut pages = backend.article_pages(
+                    limit.or(settings.page_limit),
+                    category.as_deref(),
+                    tags.as_deref(),
+                );
+                if let Some(filter) = filter {
+                    pages = pages.filter(Query::parse(filter)?);
+                }
+                if let Some(max) = max {
+                    pages = pages.max(*max);
+                }
+                return list_all(pages, table, settings, printer).await;
+            }
+
+            let resp = backend
+                .get_article_list(
+                    page.unwrap_or(1),
+                    limit.or(settings.page_limit),
+                    category.as_deref(),
+                    tags.as_deref(),
+                )
+                .await?;
+            let Some(mut articles) = resp.into_data() else {
+                return Ok(None);
+            };
+
+            table::sort(ARTICLE_COLUMNS, &mut articles.items, table)?;
+            let text = match &table.format {
+                Some(format) => {
+                    Template::resolve(format, &settings.templates)?.render_all(&articles.items)?
+                }
+                None => format!(
+                    "{}\n\n{}",
+                    table::render(
+                        ARTICLE_COLUMNS,
+                        &articles.items,
+                        table,
+                        table::terminal_width()
+                    )?,
+                    articles.page_info()
+                ),
+            };
+            printer.listing(&Described::new(text, &articles), &articles.items)?;
+            Ok(summary(table.format.is_some(), articles.count))
+        }
+
+        Action::Rm { uri, version } => {
+            backend.delete_article_version(uri, version).await?;
+            forget_versions(settings, profile, uri)?;
+            Ok(Some(format!(
+                "Article version deleted, uri: {}, version: {}",
+                uri.bold(),
+                version.bold()
+            )))
+        }
+
+        Action::Versions { uri, table } => {
+            let resp = backend.get_article_version_list(uri).await?;
+            let Some(mut versions) = resp.into_data() else {
+                return Ok(None);
+            };
+
+            table::sort(VERSION_COLUMNS, &mut versions.items, table)?;
+            let text = match &table.format {
+                Some(format) => {
+                    Template::resolve(format, &settings.templates)?.render_all(&versions.items)?
+                }
+                None => {
+                    printer.note(&format!("Article {} version list:", uri.bold()))?;
+                    table::render(
+                        VERSION_COLUMNS,
+                        &versions.items,
+                        table,
+                        table::terminal_width(),
+                    )?
+                }
+            };
+            printer.listing(&Described::new(text, &versions), &versions.items)?;
+            Ok(summary(table.format.is_some(), versions.count))
+        }
+
+        Action::Set {
+            uri,
+            article_option,
+        } => {
+            if article_option.public {
+                backend.set_article_visibility(uri, true).await?;
+            }
+
+            if article_option.private {
+                backend.set_article_visibility(uri, false).await?;
+            }
+
+            if let Some(tags) = &article_option.tags {
+                backend.set_article_tags(uri, tags).await?;
+            }
+
+            if let Some(category) = &article_option.category {
+                backend.set_article_category(uri, category).await?;
+            }
+
+            if let Some(version) = &article_option.version {
+                backend.set_article_version(uri, version).await?;
+            }
+
+            Ok(Some("successful.".to_string()))
+        }
+
+        Action::Search {
+            query,
+            filter,
+            limit,
+            refresh,
+        } => {
+            let mut pages = backend.article_pages(settings.page_limit, None, None);
+            if let Some(filter) = filter {
+                pages = pages.filter(Query::parse(filter)?);
+            }
+            let mut articles = Vec::new();
+            while let Some(page) = pages.next().await? {
+                articles.extend(page.items);
+            }
+
+            let mut cache = version_cache(settings, profile)?;
+            if *refresh {
+                cache = cache.map(VersionCache::refresh);
+            }
+            let versions = search::versions(backend, &articles, cache.as_ref()).await?;
+
+            let mut results = Search::new(&query.join(" ")).rank(articles, versions);
+            if let Some(limit) = limit {
+                results.items.truncate(*limit);
+            }
+
+            if !results.items.is_empty() || printer.format().is_structured() {
+                printer.listing(&results, &results.items)?;
+            }
+            if results.items.len() < results.count {
+                Ok(Some(format!(
+                    "Search done. Showing {} of {} matches.",
+                    results.items.len(),
+                    results.count
+                )))
+            } else {
+                Ok(Some(format!(
+                    "Search done. Total {} matches.",
+                    results.count
+                )))
+            }
+        }
+
+        Action::Category { action } => category(backend, action, settings, printer).await,
+
+        _ => Ok(None),
+    }
+}
+
+/// The cache of the version lists searched by `search`, one per profile.
+///
+/// There is none for `$BLC_TOKEN`, unless `cache_dir` is configured.
+fn version_cache(settings: &Settings, profile: &str) -> Result<Option<VersionCache>> {
+    let dir = match &settings.cache_dir {
+        Some(dir) => config::expand_home(dir)?,
+        None if config::env_token().is_some() => return Ok(None),
+        None => config::default_cache_dir()?,
+    };
+    Ok(Some(VersionCache::new(dir.join(profile))))
+}
+
+/// Drop the cached version list of `uri` after its versions changed.
+fn forget_versions(settings: &Settings, profile: &str, uri: &str) -> Result<()> {
+    match version_cache(settings, profile) {
+        Ok(Some(cache)) => cache.remove(uri),
+        // 没有主目录时也就没有缓存
+        Ok(None) | Err(Error::NoHomeDir) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Print every page of `pages`, for `list --all` and `list --max`.
+///
+/// Text and `ndjson` output is written as the pages arrive, a table with its header once.
+/// Sorted output and documents need all the articles first and are written at the end.
+async fn list_all<B: BlogBackend, W: Write>(
+    mut pages: ArticlePages<'_, B>,
+    table: &TableArgs,
+    settings: &Settings,
+    printer: &mut Printer<W>,
+) -> Result<Option<String>> {
+    let template = table
+        .format
+        .as_deref()
+        .map(|format| Template::resolve(format, &settings.templates))
+        .transpose()?;
+    let stream = match printer.format() {
+        OutputFormat::Ndjson => true,
+        OutputFormat::Text => table.sort.is_none() && !table.reverse,
+        OutputFormat::Json | OutputFormat::Yaml => false,
+    };
+    let mut rows = match (stream, printer.format(), &template) {
+        (true, OutputFormat::Text, None) => Some(table::StreamedTable::new(
+            ARTICLE_COLUMNS,
+            table,
+            table::terminal_width(),
+        )?),
+        _ => None,
+    };
+
+    let mut all: Option<Articles> = None;
+    let mut shown = 0;
+    while let Some(mut articles) = pages.next().await? {
+        shown += articles.items.len();
+        if stream && !articles.items.is_empty() {
+            let text = match (&template, &mut rows) {
+                (Some(template), _) => template.render_all(&articles.items)?,
+                (None, Some(rows)) => rows.render(&articles.items),
+                (None, None) => String::new(),
+            };
+            printer.listing(&Described::new(text, &articles), &articles.items)?;
+            // 已输出的文章不再保留, 只记录计数
+            articles.items.clear();
+        }
+
+        match &mut all {
+            Some(all) => {
+                all.count = articles.count;
+                all.items.append(&mut articles.items);
+            }
+            None => all = Some(articles),
+        }
+    }
+
+    let Some(mut articles) = all else {
+        return Ok(None);
+    };
+    articles.next = pages.truncated();
+
+    if !stream {
+        table::sort(ARTICLE_COLUMNS, &mut articles.items, table)?;
+        let text = match &template {
+            Some(template) => template.render_all(&articles.items)?,
+            None => table::render(
+                ARTICLE_COLUMNS,
+                &articles.items,
+                table,
+                table::terminal_width(),
+            )?,
+        };
+        printer.listing(&Described::new(text, &articles), &articles.items)?;
+    }
+
+    if template.is_some() {
+        Ok(None)
+    } else if shown < articles.count as usize {
+        // 被 --where 或 --max 过滤掉了一部分
+        Ok(Some(format!(
+            "Query done. Showing {shown} of {} items.",
+            articles.count
+        )))
+    } else {
+        Ok(summary(false, articles.count))
+    }
+}
+
+async fn category<B: BlogBackend, W: Write>(
+    backend: &B,
+    action: &CategoryAction,
+    settings: &Settings,
+    printer: &mut Printer<W>,
+) -> Result<Option<String>> {
+    match action {
+        CategoryAction::List { format } => {
+            let resp = backend.get_category_list().await?;
+            match resp.data() {
+                Some(categories) => {
+                    match format {
+                        Some(format) => {
+                            let text = Template::resolve(format, &settings.templates)?
+                                .render_all(&categories.items)?;
+                            printer.listing(&Described::new(text, categories), &categories.items)?
+                        }
+                        None => printer.listing(categories, &categories.items)?,
+                    }
+                    Ok(summary(format.is_some(), categories.count))
+                }
+                None => Ok(None),
+            }
+        }
+
+        CategoryAction::Show { slug } => {
+            let category = backend.get_category(slug).await?;
+            let Some(category) = category.data() else {
+                return Ok(None);
+            };
+
+            let mut items = Vec::new();
+            let mut pages = backend.article_pages(None, Some(slug), None);
+            while let Some(page) = pages.next().await? {
+                items.extend(page.items);
+            }
+            let articles = Articles {
+                count: items.len() as u32,
+                page: 1,
+                items,
+                next: false,
+                prev: false,
+            };
+
+            printer.record(&CategoryDetail {
+                category,
+                articles: &articles,
+            })?;
+            Ok(Some(format!("Query done. Total {} items.", articles.count)))
+        }
+
+        CategoryAction::Create { slug, name } => {
+            backend.create_category(slug, name).await?;
+            Ok(Some(format!("Category created, slug: {}", slug.bold())))
+        }
+
+        CategoryAction::Rename { slug, name } => {
+            backend.rename_category(slug, name).await?;
+            Ok(Some(format!(
+                "Category renamed, slug: {}, name: {}",
+                slug.bold(),
+                name.bold()
+            )))
+        }
+
+        CategoryAction::Delete { slug, reassign } => {
+            if reassign.as_ref() == Some(slug) {
+                return Err(Error::ReassignToSelf(slug.clone()));
+            }
+
+            let resp = backend.get_category(slug).await?;
+            let count = resp.data().as_ref().map_or(0, |c| c.article_count);
+
+            // 先确认目标分类存在, 避免移动了一部分文章后才失败
+            if let Some(target) = reassign {
+                backend.get_category(target).await?;
+            }
+
+            if count > 0 {
+                let Some(target) = reassign.as_deref() else {
+                    return Err(Error::CategoryNotEmpty {
+                        slug: slug.clone(),
+                        count,
+                    });
+                };
+
+                let uris = backend.get_category_article_uris(slug).await?;
+                for uri in &uris {
+                    backend.set_article_category(uri, target).await?;
+                }
+                printer.note(&format!(
+                    "Moved {} article(s) to {}",
+                    uris.len(),
+                    target.bold()
+                ))?;
+            }
+
+            backend.delete_category(slug).await?;
+            Ok(Some(format!("Category deleted, slug: {}", slug.bold())))
+        }
+    }
+}
+
+/// The closing message of a listing. Templated output is left alone, so that it can be
+/// used by scripts as it is.
+fn summary(templated: bool, count: u32) -> Option<String> {
+    (!templated).then(|| format!("Query done. Total {count} items."))
+}
+
+/// A category together with all of its articles, shown by `category show`.
+#[derive(Serialize)]
+struct CategoryDetail<'a> {
+    category: &'a CategoryItem,
+    articles: &'a Articles,
+}
+
+impl Display for CategoryDetail<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.category)?;
+        for (i, article) in self.articles.items.iter().enumerate() {
+            writeln!(f, "{}. {}", i + 1, article)?;
+        }
+        Ok(())
+    }
+}
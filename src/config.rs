@@ -1,36 +1,108 @@
+use crate::api::Session;
 use crate::error::Error;
 use crate::error::Result;
+use crate::settings::{CredentialStore, Settings};
+use age::secrecy::SecretString;
 use binrw::{binrw, BinRead, BinWrite};
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use colored::Colorize;
+use std::io::{Cursor, Write};
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
+use std::sync::OnceLock;
 use std::{env, fs};
-pub static CFG: LazyLock<Config> = LazyLock::new(Config::init);
+
+/// Files written by the encrypted store start with the age header.
+const AGE_MAGIC: &[u8] = b"age-encryption.org/";
+
+/// Profile used when neither `--profile`, `$BLC_PROFILE` nor the configuration select one.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Magic bytes at the start of every plain credential file.
+const MAGIC: &[u8; 4] = b"AUTH";
+
+/// Version byte written after [`MAGIC`].
+///
+/// Version 0 files only carry the token, version 1 adds the API endpoint and
+/// version 2 the token expiry. Older files are read and rewritten in the current layout.
+const FORMAT_VERSION: u8 = 2;
 
 #[binrw]
 #[brw(big)]
+#[derive(Clone)]
 pub struct Config {
     header: [u8; 5],
     timestamp: i64,
     token_len: u32,
 
     // wrapping_add/wrapping_sub简单的移位混淆
-    #[br(count = token_len, map = |s:Vec<u8>| String::from_utf8(s.iter().map(|c| c.wrapping_add(5)).collect()).unwrap() )]
+    #[br(count = token_len, try_map = |s:Vec<u8>| String::from_utf8(s.iter().map(|c| c.wrapping_add(5)).collect()) )]
     #[bw(map = |s| s.clone().into_bytes().iter().map(|c|c.wrapping_sub(5)).collect::<Vec<u8>>() )]
     pub token: String,
+
+    #[br(if(header[4] >= 1))]
+    endpoint_len: u32,
+
+    #[br(if(header[4] >= 1), count = endpoint_len, try_map = String::from_utf8)]
+    #[bw(map = |s| s.clone().into_bytes())]
+    pub endpoint: String,
+
+    // 毫秒时间戳, 0表示有效期未知
+    #[br(if(header[4] >= 2))]
+    expires_at: i64,
 }
 
 impl Config {
-    pub fn init() -> Self {
-        let mut file = fs::File::open(get_file_path()).unwrap_or_else(|_| {
-            eprintln!("{}", Error::UnAuth);
-            std::process::exit(1)
-        });
-        Config::read(&mut file).unwrap()
+    /// Parse a plain credential file, validating the magic and version first.
+    fn decode(bytes: &[u8]) -> core::result::Result<Self, String> {
+        if bytes.len() < 5 || &bytes[..4] != MAGIC {
+            return Err("not a blc credential file".to_string());
+        }
+
+        let version = bytes[4];
+        if version > FORMAT_VERSION {
+            return Err(format!(
+                "format version {version} is newer than the supported version {FORMAT_VERSION}"
+            ));
+        }
+
+        let mut cursor = Cursor::new(bytes);
+        let cfg = Config::read(&mut cursor).map_err(|e| {
+            if e.is_eof() {
+                "the file is truncated".to_string()
+            } else {
+                e.root_cause().to_string()
+            }
+        })?;
+
+        if cursor.position() != bytes.len() as u64 {
+            return Err("unexpected data after the end of the file".to_string());
+        }
+        if cfg.token.is_empty() {
+            return Err("the token is empty".to_string());
+        }
+
+        Ok(cfg)
+    }
+
+    /// Whether the given profile has saved login state, without decrypting it.
+    pub fn exists(name: &str) -> bool {
+        profile_file_path(name).is_ok_and(|path| path.exists())
     }
 
-    pub fn clear() -> Result<()> {
-        let path = get_dir_path();
+    /// Remove the saved login state of the given profile.
+    pub fn clear_profile(name: &str) -> Result<()> {
+        let path = profile_file_path(name)?;
+
+        if path.exists() {
+            fs::remove_file(path).map_err(Error::from)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Remove the saved login state of every profile.
+    pub fn clear_all() -> Result<()> {
+        let path = get_dir_path()?;
 
         if path.exists() {
             fs::remove_dir_all(path).map_err(Error::from)
@@ -39,31 +111,58 @@ impl Config {
         }
     }
 
-    pub fn new(token: &str) -> Self {
+    /// Login state for a freshly issued token, valid for `lifetime` if that is known.
+    pub fn new(token: &str, endpoint: &str, lifetime: Option<Duration>) -> Self {
         let token = token.to_string();
-        let version = env!("CARGO_PKG_VERSION_MAJOR").parse::<u8>().unwrap();
+        let endpoint = endpoint.to_string();
+        let now = Utc::now();
+
+        let expires_at = lifetime.map_or(0, |lifetime| (now + lifetime).timestamp_millis());
 
         Config {
-            header: [b'A', b'U', b'T', b'H', version],
-            timestamp: Utc::now().timestamp_millis(),
+            header: [MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], FORMAT_VERSION],
+            timestamp: now.timestamp_millis(),
             token_len: token.len() as u32,
             token,
+            endpoint_len: endpoint.len() as u32,
+            endpoint,
+            expires_at,
         }
     }
 
-    pub fn save(&self) -> Result<()> {
-        let path = get_file_path();
+    /// When the token was issued.
+    pub fn logged_in_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.timestamp).unwrap_or_default()
+    }
 
-        let mut file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(path)
-            .unwrap();
+    /// When the token stops being accepted, if known.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        (self.expires_at > 0)
+            .then(|| DateTime::from_timestamp_millis(self.expires_at))
+            .flatten()
+    }
 
-        self.write(&mut file).unwrap();
+    /// Print a warning when the token has expired or is about to.
+    ///
+    /// "About to" means less than a fifth of its lifetime is left.
+    pub fn warn_expiry(&self) {
+        let Some(expires_at) = self.expires_at() else {
+            return;
+        };
 
-        Ok(())
+        let remaining = expires_at - Utc::now();
+        if remaining <= Duration::zero() {
+            eprintln!(
+                "{} The login token has expired, you will be asked to log in again.",
+                "!".yellow().bold()
+            );
+        } else if remaining < (expires_at - self.logged_in_at()) / 5 {
+            eprintln!(
+                "{} The login token expires in {}, run `blc login` to renew it.",
+                "!".yellow().bold(),
+                format_duration(remaining)
+            );
+        }
     }
 
     // fn check(&self) -> Result<()> {
@@ -72,19 +171,313 @@ impl Config {
     // }
 }
 
-fn get_dir_path() -> PathBuf {
-    let home_dir = env::var("HOME")
-        .or_else(|_| env::var("USERPROFILE"))
-        .unwrap();
-    Path::new(&home_dir).join(".blc")
+/// The saved login states, read and written for the profile selected for this run.
+///
+/// The login state of that profile is read once, and an encrypted store only asks for
+/// its passphrase once. A clone keeps what has been read so far.
+#[derive(Clone)]
+pub struct Credentials {
+    profile: String,
+    store: CredentialStore,
+    token_lifetime: Option<u64>,
+    current: OnceLock<Option<Config>>,
+    passphrase: OnceLock<String>,
+}
+
+impl Credentials {
+    /// The login states of `profile`, stored as `credential_store` of the settings says.
+    pub fn new(profile: &str, settings: &Settings) -> Result<Self> {
+        check_profile_name(profile)?;
+
+        Ok(Credentials {
+            profile: profile.to_string(),
+            store: settings.credential_store,
+            token_lifetime: settings.token_lifetime,
+            current: OnceLock::new(),
+            passphrase: OnceLock::new(),
+        })
+    }
+
+    /// The profile selected for this run.
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Read the saved login state of the selected profile, if there is one.
+    pub fn load(&self) -> Result<Option<&Config>> {
+        if let Some(cfg) = self.current.get() {
+            return Ok(cfg.as_ref());
+        }
+
+        let cfg = self.load_profile(&self.profile)?;
+        Ok(self.current.get_or_init(|| cfg).as_ref())
+    }
+
+    /// Read the saved login state of the given profile, if there is one.
+    pub fn load_profile(&self, name: &str) -> Result<Option<Config>> {
+        let path = profile_file_path(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        check_permissions(&path, 0o600)?;
+        let bytes = fs::read(&path)?;
+
+        let encrypted = bytes.starts_with(AGE_MAGIC);
+        let bytes = if encrypted {
+            age::decrypt(&age::scrypt::Identity::new(self.passphrase(false)?), &bytes)
+                .map_err(|e| Error::Credential(e.to_string()))?
+        } else {
+            bytes
+        };
+
+        let mut cfg = Config::decode(&bytes).map_err(|reason| Error::CorruptCredentials {
+            path: path.clone(),
+            reason,
+        })?;
+
+        if cfg.header[4] < FORMAT_VERSION {
+            cfg.header[4] = FORMAT_VERSION;
+            cfg.endpoint_len = cfg.endpoint.len() as u32;
+            self.save_to(&cfg, &path)?;
+            eprintln!(
+                "{} Credentials of profile {} upgraded to format version {}.",
+                "!".yellow().bold(),
+                name.bold(),
+                FORMAT_VERSION
+            );
+        } else if !encrypted && self.store == CredentialStore::Encrypted {
+            // 旧的明文文件在启用加密存储后自动迁移
+            self.save_to(&cfg, &path)?;
+            eprintln!(
+                "{} Credentials of profile {} migrated to the encrypted store.",
+                "!".yellow().bold(),
+                name.bold()
+            );
+        }
+
+        Ok(Some(cfg))
+    }
+
+    /// Path of the file holding the login state of the selected profile.
+    pub fn path(&self) -> Result<PathBuf> {
+        profile_file_path(&self.profile)
+    }
+
+    /// Save the login state for a freshly issued token of the selected profile.
+    ///
+    /// When the server does not say how long the token is valid, the `token_lifetime`
+    /// configuration key is used.
+    pub fn save_session(&self, session: &Session, endpoint: &str) -> Result<()> {
+        let lifetime = session.lifetime.or_else(|| {
+            self.token_lifetime
+                .map(|secs| Duration::seconds(secs as i64))
+        });
+        self.save(&Config::new(&session.token, endpoint, lifetime))
+    }
+
+    /// Save the login state of the selected profile.
+    pub fn save(&self, cfg: &Config) -> Result<()> {
+        self.save_to(cfg, &self.path()?)
+    }
+
+    fn save_to(&self, cfg: &Config, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir)?;
+        }
+
+        let mut bytes = Cursor::new(Vec::new());
+        cfg.write(&mut bytes).unwrap();
+        let mut bytes = bytes.into_inner();
+
+        if self.store == CredentialStore::Encrypted {
+            bytes = age::encrypt(&age::scrypt::Recipient::new(self.passphrase(true)?), &bytes)
+                .map_err(|e| Error::Credential(e.to_string()))?;
+        }
+
+        write_private(path, &bytes)
+    }
+
+    /// Remove the saved login state of the selected profile.
+    pub fn clear(&self) -> Result<()> {
+        Config::clear_profile(&self.profile)
+    }
+
+    /// The passphrase of the encrypted store, from `$BLC_PASSPHRASE` or an interactive prompt.
+    fn passphrase(&self, confirm: bool) -> Result<SecretString> {
+        if let Some(passphrase) = self.passphrase.get() {
+            return Ok(SecretString::from(passphrase.clone()));
+        }
+
+        let passphrase = match env::var("BLC_PASSPHRASE") {
+            Ok(passphrase) => passphrase,
+            Err(_) => {
+                let passphrase = rpassword::prompt_password("credential passphrase: ")?;
+                if confirm && rpassword::prompt_password("confirm passphrase: ")? != passphrase {
+                    return Err(Error::Credential("passphrases do not match".to_string()));
+                }
+                passphrase
+            }
+        };
+
+        if passphrase.is_empty() {
+            return Err(Error::Credential("empty passphrase".to_string()));
+        }
+
+        Ok(SecretString::from(
+            self.passphrase.get_or_init(|| passphrase).clone(),
+        ))
+    }
+}
+
+fn home_dir() -> Result<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .ok_or(Error::NoHomeDir)
+}
+
+fn get_dir_path() -> Result<PathBuf> {
+    Ok(home_dir()?.join(".blc"))
+}
+
+/// `path` with a leading `~` replaced by the home directory, e.g. for `cache_dir`.
+pub fn expand_home(path: &Path) -> Result<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => Ok(home_dir()?.join(rest)),
+        Err(_) => Ok(path.to_path_buf()),
+    }
+}
+
+/// `~/.blc/cache`, used when `cache_dir` is not configured.
+pub fn default_cache_dir() -> Result<PathBuf> {
+    Ok(get_dir_path()?.join("cache"))
+}
+
+/// Create a directory (and its parents) that only the owner can access.
+pub(crate) fn create_private_dir(path: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+    builder.create(path)?;
+    restrict_permissions(path, 0o700)
+}
+
+/// Write a file that only the owner can read, e.g. credentials or cached version notes.
+pub(crate) fn write_private(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+    restrict_permissions(path, 0o600)?;
+    file.write_all(bytes)?;
+
+    Ok(())
+}
+
+/// Tighten the permissions of a credential path, warning about files that were too open.
+#[cfg(unix)]
+fn check_permissions(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let current = fs::metadata(path)?.permissions().mode() & 0o777;
+    if current & 0o077 == 0 {
+        return Ok(());
+    }
+
+    eprintln!(
+        "{} {} is accessible by other users ({:o}), restricting it to {:o}.",
+        "!".yellow().bold(),
+        path.display(),
+        current,
+        mode
+    );
+    restrict_permissions(path, mode)
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|_| Error::InsecurePermissions(path.to_path_buf()))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// The default profile keeps the historical `~/.blc/blc.b`, the others live in `~/.blc/profiles/`.
+fn profile_file_path(name: &str) -> Result<PathBuf> {
+    let dir = get_dir_path()?;
+    if name == DEFAULT_PROFILE {
+        Ok(dir.join("blc.b"))
+    } else {
+        Ok(dir.join("profiles").join(format!("{name}.b")))
+    }
+}
+
+/// Token supplied through `$BLC_TOKEN`.
+///
+/// It is used as is and never written to disk, for CI pipelines and other ephemeral environments.
+pub fn env_token() -> Option<String> {
+    env::var("BLC_TOKEN").ok().filter(|token| !token.is_empty())
+}
+
+/// Profile names must be usable as file names.
+pub fn check_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidProfileName(name.to_string()))
+    }
+}
+
+/// Names of all profiles that have saved credentials.
+pub fn saved_profiles() -> Result<Vec<String>> {
+    let dir = get_dir_path()?;
+    let mut names = Vec::new();
+
+    if dir.join("blc.b").exists() {
+        names.push(DEFAULT_PROFILE.to_string());
+    }
+
+    if let Ok(entries) = fs::read_dir(dir.join("profiles")) {
+        names.extend(entries.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            (path.extension()? == "b").then(|| path.file_stem()?.to_str().map(String::from))?
+        }));
+    }
+
+    names.sort();
+    Ok(names)
 }
 
-fn get_file_path() -> PathBuf {
-    let path = get_dir_path();
-    let config_file_path: std::path::PathBuf = path.join("blc.b");
+/// Human readable duration such as `3d 4h` or `25m`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
 
-    if !path.exists() {
-        fs::create_dir_all(&path).expect("初始化配置文件失败");
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{secs}s"),
+        (0, 0, m) => format!("{m}m"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, h, _) => format!("{d}d {h}h"),
     }
-    config_file_path
 }
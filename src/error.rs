@@ -2,23 +2,209 @@ use std::fmt::{Debug, Display};
 
 use clap::CommandFactory;
 
+use crate::api::response::ResponseCode;
 use crate::{api, Cli};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Class of an error, which decides the exit code of the process.
+///
+/// Exit code 2 is shared with the usage errors reported by the argument parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Invalid local input, e.g. a configuration value, template or `--where` expression.
+    Usage,
+
+    /// Not logged in, a rejected token or a wrong password.
+    Auth,
+
+    NotFound,
+
+    /// Parameters or an action refused by the server.
+    InvalidParams,
+
+    /// The server could not be reached or did not answer in time.
+    Network,
+
+    /// Reading or writing local files.
+    Io,
+
+    /// The server failed or answered with something unexpected.
+    Server,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Usage => 2,
+            ErrorKind::Auth => 3,
+            ErrorKind::NotFound => 4,
+            ErrorKind::InvalidParams => 5,
+            ErrorKind::Network => 6,
+            ErrorKind::Io => 7,
+            ErrorKind::Server => 8,
+        }
+    }
+
+    /// Name used in `--output json` error objects.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Usage => "usage",
+            ErrorKind::Auth => "auth",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::InvalidParams => "invalid_params",
+            ErrorKind::Network => "network",
+            ErrorKind::Io => "io",
+            ErrorKind::Server => "server",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
-    ResponseError { code: String, message: String },
+    ResponseError {
+        code: ResponseCode,
+        message: String,
+    },
 
     // request error
     HTTPError(reqwest::Error),
 
+    // response body is not the expected JSON
+    InvalidResponse(serde_json::Error),
+
+    UnexpectedContentType {
+        content_type: String,
+        url: String,
+    },
+
+    // HTTP 404 without an envelope, the path does not exist on the server
+    EndpointNotFound(String),
+
+    // HTTP 413, e.g. an upload above the limit of the server or its proxy
+    PayloadTooLarge,
+
+    // HTTP 502, 503 or 504 without an envelope
+    GatewayError(reqwest::StatusCode),
+
+    // any other HTTP error status without an envelope
+    HttpStatus {
+        status: reqwest::StatusCode,
+        url: String,
+    },
+
     // open file
     IO(std::io::Error),
 
     UnAuth,
 
     ServiceError,
+
+    NoEndpoint,
+
+    InvalidEndpoint(String),
+
+    InvalidProxy(String),
+
+    // CA bundle, client certificate or key that cannot be read
+    InvalidCertificate {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    CategoryNotEmpty {
+        slug: String,
+        count: u32,
+    },
+
+    // `category delete <slug> --reassign <slug>`
+    ReassignToSelf(String),
+
+    // invalid configuration file or value
+    Config(config::ConfigError),
+
+    UnknownConfigKey(String),
+
+    // a known key without a value, for `config get`
+    UnsetConfigKey(String),
+
+    NoHomeDir,
+
+    MissingCategory,
+
+    UnknownColumn {
+        column: String,
+        available: Vec<&'static str>,
+    },
+
+    InvalidTemplate(String),
+
+    InvalidQuery(String),
+
+    InvalidProfileName(String),
+
+    UnknownProfile(String),
+
+    // encrypted credential store
+    Credential(String),
+
+    InsecurePermissions(std::path::PathBuf),
+
+    CorruptCredentials {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ResponseError { code, .. } => match code {
+                ResponseCode::Unauthorised | ResponseCode::PwdError => ErrorKind::Auth,
+                ResponseCode::ResourceNotExist => ErrorKind::NotFound,
+                ResponseCode::InvalidParameters | ResponseCode::InvalidAction => {
+                    ErrorKind::InvalidParams
+                }
+                ResponseCode::Ok
+                | ResponseCode::Unimplemented
+                | ResponseCode::InternalServiceError
+                | ResponseCode::Unknown(_) => ErrorKind::Server,
+            },
+            // 响应无法解析说明服务端有问题, 而不是网络; 读取响应体超时仍是网络问题
+            Error::HTTPError(e) if e.is_decode() && !e.is_timeout() => ErrorKind::Server,
+            Error::HTTPError(_) => ErrorKind::Network,
+            Error::IO(_) | Error::InsecurePermissions(_) | Error::NoHomeDir => ErrorKind::Io,
+            Error::UnAuth | Error::Credential(_) | Error::CorruptCredentials { .. } => {
+                ErrorKind::Auth
+            }
+            Error::InvalidResponse(_)
+            | Error::UnexpectedContentType { .. }
+            | Error::HttpStatus { .. }
+            | Error::ServiceError => ErrorKind::Server,
+            Error::EndpointNotFound(_) => ErrorKind::NotFound,
+            Error::PayloadTooLarge => ErrorKind::InvalidParams,
+            Error::GatewayError(_) => ErrorKind::Network,
+            Error::UnknownProfile(_) => ErrorKind::NotFound,
+            Error::CategoryNotEmpty { .. } | Error::MissingCategory => ErrorKind::InvalidParams,
+            Error::NoEndpoint
+            | Error::InvalidEndpoint(_)
+            | Error::InvalidProxy(_)
+            | Error::InvalidCertificate { .. }
+            | Error::Config(_)
+            | Error::UnknownConfigKey(_)
+            | Error::UnsetConfigKey(_)
+            | Error::UnknownColumn { .. }
+            | Error::InvalidTemplate(_)
+            | Error::InvalidQuery(_)
+            | Error::InvalidProfileName(_)
+            | Error::ReassignToSelf(_) => ErrorKind::Usage,
+        }
+    }
+
+    /// Exit code of the process when the command fails with this error.
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -27,6 +213,12 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<config::ConfigError> for Error {
+    fn from(value: config::ConfigError) -> Self {
+        Error::Config(value)
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
         Error::HTTPError(value)
@@ -36,7 +228,7 @@ impl From<reqwest::Error> for Error {
 impl<T> From<api::response::Response<T>> for Error {
     fn from(value: api::response::Response<T>) -> Self {
         Error::ResponseError {
-            code: value.code(),
+            code: value.code().clone(),
             message: value.message(),
         }
     }
@@ -47,11 +239,30 @@ impl Display for Error {
         match self {
             Error::HTTPError(e) => write!(f, "HTTP request error:{e}"),
             Error::IO(e) => write!(f, "io error: {e}"),
-            Error::ResponseError { code: _, message } => write!(f, "{}", message),
+            Error::InvalidResponse(e) => write!(f, "error decoding response body: {e}"),
+            Error::UnexpectedContentType { content_type, url } => write!(
+                f,
+                "Expected a JSON response from {url}, got {content_type}. Check the API endpoint."
+            ),
+            Error::EndpointNotFound(url) => write!(
+                f,
+                "Not found: {url} (HTTP 404). Check the API endpoint and the version of the server."
+            ),
+            Error::PayloadTooLarge => write!(
+                f,
+                "The request is too large for the server (HTTP 413). Its upload limit, or that of a proxy in front of it, must be raised."
+            ),
+            Error::GatewayError(status) => write!(
+                f,
+                "The server is unavailable behind its gateway (HTTP {status}). Try again later."
+            ),
+            Error::HttpStatus { status, url } => write!(f, "HTTP {status} from {url}"),
+            Error::ResponseError { code, message } => write!(f, "{message} ({code})"),
             Error::UnAuth => {
-                writeln!(f, "You must login first.")?;
+                write!(f, "You must login first.")?;
+                // 帮助信息随错误一起输出, 不能混进标准输出中的结构化结果
                 if let Some(login) = Cli::command().find_subcommand("login") {
-                    let _ = login.to_owned().print_help();
+                    write!(f, "\n{}", login.to_owned().render_help())?;
                 }
 
                 Ok(())
@@ -60,6 +271,63 @@ impl Display for Error {
                 f,
                 "The api response is OK, but the response header lacks necessary data."
             ),
+            Error::CategoryNotEmpty { slug, count } => write!(
+                f,
+                "Category {slug} still contains {count} article(s). Use --reassign <SLUG> to move them first."
+            ),
+            Error::ReassignToSelf(slug) => write!(
+                f,
+                "Cannot reassign the articles of {slug} to the category being deleted. Pass another category to --reassign."
+            ),
+            Error::Config(e) => write!(f, "configuration error: {e}"),
+            Error::UnknownConfigKey(key) => write!(f, "Unknown configuration key: {key}"),
+            Error::UnsetConfigKey(key) => write!(f, "`{key}` is not set."),
+            Error::NoHomeDir => write!(f, "Cannot locate the home directory."),
+            Error::MissingCategory => write!(
+                f,
+                "No category given. Pass --category <SLUG> or set `default_category` in the configuration."
+            ),
+            Error::UnknownColumn { column, available } => write!(
+                f,
+                "Unknown column: {column}. Available columns: {}",
+                available.join(", ")
+            ),
+            Error::InvalidTemplate(reason) => write!(f, "Invalid template: {reason}"),
+            Error::InvalidQuery(reason) => write!(f, "Invalid --where expression: {reason}"),
+            Error::InvalidProfileName(name) => write!(
+                f,
+                "Invalid profile name: {name}. Only letters, digits, '-' and '_' are allowed."
+            ),
+            Error::UnknownProfile(name) => write!(
+                f,
+                "Unknown profile: {name}. Add it with `blc profile add {name}` first."
+            ),
+            Error::Credential(e) => write!(f, "credential store error: {e}"),
+            Error::InsecurePermissions(path) => write!(
+                f,
+                "{} is accessible by other users and its permissions cannot be restricted.",
+                path.display()
+            ),
+            Error::CorruptCredentials { path, reason } => write!(
+                f,
+                "The saved login state at {} cannot be read: {reason}. Run `blc login` to sign in again.",
+                path.display()
+            ),
+            Error::InvalidEndpoint(url) => write!(
+                f,
+                "Invalid API endpoint: {url}. Expected an absolute URL like https://blog.example.com/api/v1."
+            ),
+            Error::InvalidProxy(url) => write!(
+                f,
+                "Invalid proxy: {url}. Expected a URL like http://proxy.example.com:3128."
+            ),
+            Error::InvalidCertificate { path, reason } => {
+                write!(f, "Cannot use the certificate {}: {reason}", path.display())
+            }
+            Error::NoEndpoint => write!(
+                f,
+                "No API endpoint configured. Pass --api <URL> or set the environment variable $BLC_API."
+            ),
         }
     }
 }
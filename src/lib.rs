@@ -1,8 +1,15 @@
 pub mod api;
 pub mod cli;
+pub mod commands;
 pub mod config;
 pub mod error;
+pub mod output;
+pub mod query;
+pub mod search;
+pub mod session;
+pub mod settings;
+pub mod table;
+pub mod template;
 
 pub use cli::*;
-pub use config::CFG;
 pub use error::*;
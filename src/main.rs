@@ -1,134 +1,521 @@
-use blc::config::Config;
-use blc::{api, Action, Cli};
+use blc::api::response::format_detatime_from_timestamp;
+use blc::api::{BlcClient, BlogBackend};
+use blc::config::{self, Config, Credentials};
+use blc::output::{Described, OutputFormat, Printer};
+use blc::settings::{CredentialStore, Scope, Settings};
+use blc::{commands, session, Action, Cli, ConfigAction, NetworkArgs, ProfileAction};
 use blc::{Error, Result};
-use clap::Parser;
+use chrono::Utc;
+use clap::{CommandFactory, Parser};
 use colored::Colorize;
+use serde_json::json;
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+use std::time::Instant;
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Output format of this run, also used when exiting on an error.
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let loaded = Settings::load();
+    let args = std::env::args().collect();
+    let args = match &loaded {
+        Ok(settings) => settings.expand_aliases(args, &global_value_flags()),
+        Err(_) => Settings::aliases_only().expand_aliases(args, &global_value_flags()),
+    };
+    let cli = Cli::parse_from(args);
+
+    let settings = match loaded {
+        Ok(settings) => settings,
+        // 配置有误时仍允许用 config 命令查看和修正
+        Err(e) if matches!(cli.action, Action::Config { .. }) => {
+            eprintln!("{} {e}", "!".yellow().bold());
+            Settings::aliases_only()
+        }
+        Err(e) => {
+            FORMAT.get_or_init(|| cli.output.unwrap_or_default());
+            on_error(e)
+        }
+    };
+    settings.color.apply();
+    init_tracing(cli.verbose + cli.verbose_short);
+
+    let format = cli.output.unwrap_or(settings.output);
+    FORMAT.get_or_init(|| format);
+    if format.is_structured() {
+        colored::control::set_override(false);
+    }
+    let mut printer = Printer::new(format, std::io::stdout());
+
+    let profile = cli
+        .profile
+        .as_deref()
+        .or(settings.profile.as_deref())
+        .unwrap_or(config::DEFAULT_PROFILE);
+    let credentials = Credentials::new(profile, &settings).unwrap_or_else(on_error);
+    let explicit_api = cli.api.as_deref().or(settings.profile_api(profile));
 
     let output_info = match &cli.action {
-        Action::Init { uri, category } => {
-            api::initializetion_article(uri, category)
-                .await
+        Action::Login { password_stdin } => {
+            let password = read_password(*password_stdin).unwrap_or_else(on_error);
+
+            let client =
+                session::anonymous_client(explicit_api, &credentials, &settings, &cli.network)
+                    .unwrap_or_else(on_error);
+            let session = client.login(&password).await.unwrap_or_else(on_error);
+            credentials
+                .save_session(&session, client.endpoint())
                 .unwrap_or_else(on_error);
-            Some(format!("Article Initialization, uri: {}", uri.bold()))
+            Some("login successful.".to_string())
         }
 
-        Action::Delete { uri } => {
-            api::delete_article(uri).await.unwrap_or_else(on_error);
-            Some(format!("Article deleted, uri: {}", uri.bold()))
-        }
+        Action::Status => {
+            let endpoint = match session::endpoint(explicit_api, &credentials, &settings) {
+                Ok(endpoint) => Some(endpoint),
+                Err(Error::NoEndpoint) => None,
+                Err(e) => on_error(e),
+            };
+            let mut lines = vec![
+                format!("{:<12} {}", "Profile:", profile.bold()),
+                format!(
+                    "{:<12} {}",
+                    "Endpoint:",
+                    endpoint.as_deref().unwrap_or("none")
+                ),
+            ];
+            let mut status = json!({ "profile": profile, "endpoint": endpoint });
 
-        Action::Upload { uri, path } => {
-            api::upload_new_version(uri, path)
-                .await
+            let mut logged_in = true;
+            if config::env_token().is_some() {
+                lines.push(format!("{:<12} $BLC_TOKEN (not stored)", "Credentials:"));
+                status["credentials"] = json!("$BLC_TOKEN");
+            } else {
+                let path = credentials.path().unwrap_or_else(on_error);
+                lines.push(format!("{:<12} {}", "Credentials:", path.display()));
+                status["credentials"] = json!(path);
+
+                match credentials.load().unwrap_or_else(on_error) {
+                    Some(cfg) => {
+                        let now = Utc::now();
+                        lines.push(format!(
+                            "{:<12} {} ({} ago)",
+                            "Logged in:",
+                            format_detatime_from_timestamp(cfg.logged_in_at().timestamp_millis()),
+                            config::format_duration(now - cfg.logged_in_at())
+                        ));
+                        lines.push(match cfg.expires_at() {
+                            Some(at) if at > now => format!(
+                                "{:<12} {} (in {})",
+                                "Expires:",
+                                format_detatime_from_timestamp(at.timestamp_millis()),
+                                config::format_duration(at - now)
+                            ),
+                            Some(at) => format!(
+                                "{:<12} {} ({})",
+                                "Expires:",
+                                format_detatime_from_timestamp(at.timestamp_millis()),
+                                "expired".red()
+                            ),
+                            None => format!("{:<12} unknown", "Expires:"),
+                        });
+
+                        status["logged_in_at"] = json!(cfg.logged_in_at().to_rfc3339());
+                        status["expires_at"] = json!(cfg.expires_at().map(|at| at.to_rfc3339()));
+                    }
+                    None => {
+                        lines.push(format!("{:<12} {}", "Logged in:", "no".red()));
+                        logged_in = false;
+                    }
+                }
+            }
+            status["logged_in"] = json!(logged_in);
+
+            // 未登录或没有地址时仍输出已知的信息, 再以错误退出
+            let problem = match &endpoint {
+                _ if !logged_in => Some(Error::UnAuth),
+                None => Some(Error::NoEndpoint),
+                Some(_) => None,
+            };
+            if let Some(e) = problem {
+                printer
+                    .record(&Described::new(lines.join("\n"), &status))
+                    .unwrap_or_else(on_error);
+                return fail(printer, e);
+            }
+
+            let client = connect(explicit_api, &credentials, &settings, &cli.network);
+            let started = Instant::now();
+            let verified = client.verify_token().await;
+            if verified.is_ok() {
+                let elapsed = started.elapsed().as_millis();
+                lines.push(format!(
+                    "{:<12} {} ({} ms)",
+                    "Server:",
+                    "token accepted".green(),
+                    elapsed
+                ));
+                status["latency_ms"] = json!(elapsed);
+            }
+
+            printer
+                .record(&Described::new(lines.join("\n"), &status))
                 .unwrap_or_else(on_error);
-            Some(format!("Article uploaded, uri: {}", uri.bold()))
+            if let Err(e) = verified {
+                return fail(printer, e);
+            }
+            None
         }
 
-        Action::List {
-            page,
-            limit,
-            category,
-            tags,
+        Action::Logout {
+            all,
+            local_only,
+            all_sessions,
         } => {
-            let resp = api::get_article_list(
-                page.unwrap_or(1),
-                *limit,
-                category.as_deref(),
-                tags.as_deref(),
-            )
-            .await
-            .unwrap_or_else(on_error);
-            resp.data().as_ref().map(|articles| {
-                println!("{}", articles);
-                format!("Query done. Total {} items.", articles.count)
-            })
-        }
+            // 要清除的 profile, 未能撤销 token 的也照常清除
+            let cleared = if *all {
+                config::saved_profiles().unwrap_or_else(on_error)
+            } else {
+                vec![profile.to_string()]
+            };
+            let mut report = cleared
+                .iter()
+                .map(|name| json!({ "profile": name, "revoked": false, "error": null }))
+                .collect::<Vec<_>>();
 
-        Action::Rm { uri, version } => {
-            api::delete_article_version(uri, version)
-                .await
+            if !*local_only {
+                for (name, client) in
+                    logged_in_clients(&credentials, explicit_api, &settings, &cli.network, *all)
+                {
+                    let result = client.revoke_token(*all_sessions).await;
+                    match &result {
+                        Ok(_) => printer
+                            .note(&format!(
+                                "Token of profile {} revoked on the server.",
+                                name.bold()
+                            ))
+                            .unwrap_or_else(on_error),
+                        Err(e) => eprintln!(
+                            "{} Could not revoke the token of profile {} on the server: {}",
+                            "!".yellow().bold(),
+                            name.bold(),
+                            e
+                        ),
+                    }
+                    if let Some(entry) = report.iter_mut().find(|entry| entry["profile"] == name) {
+                        entry["revoked"] = json!(result.is_ok());
+                        entry["error"] = json!(result.err().map(|e| e.to_string()));
+                    }
+                }
+            }
+            printer
+                .data(&json!({ "all_sessions": *all_sessions, "profiles": report }))
                 .unwrap_or_else(on_error);
-            Some(format!(
-                "Article version deleted, uri: {}, version: {}",
-                uri.bold(),
-                version.bold()
-            ))
-        }
 
-        Action::Versions { uri } => {
-            let resp = api::get_article_version_list(uri)
-                .await
-                .unwrap_or_else(on_error);
-            resp.data().as_ref().map(|versions| {
-                println!("Article {} version list:\n{}", uri.bold(), versions);
-                format!("Query done. Total {} items.", versions.count)
-            })
+            if *all {
+                Config::clear_all().unwrap_or_else(on_error);
+                Some("logout of all profiles successful.".to_string())
+            } else {
+                credentials.clear().unwrap_or_else(on_error);
+                Some(format!("logout of profile {} successful.", profile.bold()))
+            }
         }
 
-        Action::Set {
-            uri,
-            article_option,
-        } => {
-            if article_option.public {
-                api::set_article_visibility(uri, true)
-                    .await
+        Action::Config { action } => match action {
+            ConfigAction::Get { key, show_origin } => {
+                let (value, origin) = Settings::get(key).unwrap_or_else(on_error);
+
+                let text = if *show_origin {
+                    format!("{origin}\t{value}")
+                } else {
+                    value.clone()
+                };
+                let entry = json!({ "key": key, "value": value, "origin": origin });
+                printer
+                    .record(&Described::new(text, &entry))
                     .unwrap_or_else(on_error);
+                None
             }
 
-            if article_option.private {
-                api::set_article_visibility(uri, false)
-                    .await
-                    .unwrap_or_else(on_error);
+            ConfigAction::Set {
+                key,
+                value,
+                project,
+            } => {
+                let scope = if *project {
+                    Scope::Project
+                } else {
+                    Scope::User
+                };
+                let path = Settings::set(key, value, scope).unwrap_or_else(on_error);
+                if key == "api" {
+                    warn_saved_endpoint(&credentials, &settings, value);
+                }
+                Some(format!(
+                    "{} = {} written to {}",
+                    key.bold(),
+                    value,
+                    path.display()
+                ))
             }
 
-            if let Some(tags) = &article_option.tags {
-                api::set_article_tags(uri, tags)
-                    .await
+            ConfigAction::List { show_origin } => {
+                let entries = Settings::entries().unwrap_or_else(on_error);
+                let text = entries
+                    .iter()
+                    .map(|(key, value, origin)| {
+                        if *show_origin {
+                            format!("{origin}\t{key} = {value}")
+                        } else {
+                            format!("{key} = {value}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let entries = entries
+                    .iter()
+                    .map(|(key, value, origin)| json!({ "key": key, "value": value, "origin": origin }))
+                    .collect::<Vec<_>>();
+                printer
+                    .listing(&Described::new(text, &entries), &entries)
                     .unwrap_or_else(on_error);
+                None
             }
+        },
 
-            if let Some(category) = &article_option.category {
-                api::set_article_category(uri, category)
-                    .await
+        Action::Profile { action } => match action {
+            ProfileAction::Add { name, endpoint } => {
+                config::check_profile_name(name).unwrap_or_else(on_error);
+                Settings::set(&format!("profiles.{name}.api"), endpoint, Scope::User)
                     .unwrap_or_else(on_error);
+                Some(format!("Profile added, name: {}", name.bold()))
             }
 
-            if let Some(version) = &article_option.version {
-                api::set_article_version(uri, version)
-                    .await
+            ProfileAction::Use { name } => {
+                let known = name == config::DEFAULT_PROFILE
+                    || settings.profiles.contains_key(name)
+                    || config::saved_profiles()
+                        .unwrap_or_else(on_error)
+                        .contains(name);
+                if !known {
+                    on_error::<()>(Error::UnknownProfile(name.clone()));
+                }
+
+                Settings::set("profile", name, Scope::User).unwrap_or_else(on_error);
+                Some(format!("Default profile set to {}", name.bold()))
+            }
+
+            ProfileAction::List => {
+                let mut names = config::saved_profiles().unwrap_or_else(on_error);
+                names.extend(settings.profiles.keys().cloned());
+                names.push(config::DEFAULT_PROFILE.to_string());
+                names.sort();
+                names.dedup();
+
+                let mut lines = Vec::new();
+                let mut profiles = Vec::new();
+                for name in &names {
+                    let logged_in = Config::exists(name);
+                    // 只通过 blc login --profile 创建的profile, 地址保存在登录信息中
+                    let endpoint = settings.profile_api(name).map(str::to_string).or_else(|| {
+                        logged_in
+                            .then(|| credentials.load_profile(name).ok().flatten())
+                            .flatten()
+                            .map(|cfg| cfg.endpoint)
+                    });
+
+                    let current = if name == profile { "*" } else { " " };
+                    let state = if logged_in {
+                        "logged in".green()
+                    } else {
+                        "not logged in".dimmed()
+                    };
+                    lines.push(format!(
+                        "[{current}] {name}\t{}\t{state}",
+                        endpoint.as_deref().unwrap_or_default()
+                    ));
+                    profiles.push(json!({
+                        "name": name,
+                        "current": name == profile,
+                        "endpoint": endpoint,
+                        "logged_in": logged_in,
+                    }));
+                }
+                printer
+                    .listing(&Described::new(lines.join("\n"), &profiles), &profiles)
                     .unwrap_or_else(on_error);
+                None
             }
 
-            Some("successful.".to_string())
-        } // _ => None,
-        Action::Login => {
-            let password = rpassword::prompt_password("password: ")
-                .map_err(Error::from)
-                .unwrap_or_else(on_error);
+            ProfileAction::Remove { name } => {
+                config::check_profile_name(name).unwrap_or_else(on_error);
+                let removed_settings = Settings::unset(&format!("profiles.{name}"), Scope::User)
+                    .unwrap_or_else(on_error)
+                    .is_some();
+                let removed_login = config::saved_profiles()
+                    .unwrap_or_else(on_error)
+                    .contains(name);
+                if !removed_settings && !removed_login {
+                    on_error::<()>(Error::UnknownProfile(name.clone()));
+                }
 
-            let token = api::login(&password).await.unwrap_or_else(on_error);
-            Config::new(&token).save().unwrap();
-            Some("login successful.".to_string())
-        }
+                Config::clear_profile(name).unwrap_or_else(on_error);
+                if settings.profile.as_deref() == Some(name) {
+                    Settings::unset("profile", Scope::User).unwrap_or_else(on_error);
+                }
+                Some(format!("Profile removed, name: {}", name.bold()))
+            }
+        },
 
-        Action::Logout => {
-            Config::clear().unwrap_or_else(on_error);
-            Some("logout successful.".to_string())
+        action => {
+            let client = connect(explicit_api, &credentials, &settings, &cli.network);
+            commands::run(&client, action, &settings, profile, &mut printer)
+                .await
+                .unwrap_or_else(on_error)
         }
     };
 
-    if let Some(info) = output_info {
-        println!("{} Finished: {}", "✓".green().bold(), info);
-    }
+    printer
+        .finish(output_info.as_deref())
+        .unwrap_or_else(on_error);
 
     Ok(())
 }
 
+/// Clients of the current profile, or of every profile with `all`, paired with the profile name.
+///
+/// Profiles whose login state cannot be read are skipped, they are only cleared locally.
+fn logged_in_clients(
+    credentials: &Credentials,
+    explicit_api: Option<&str>,
+    settings: &Settings,
+    network: &NetworkArgs,
+    all: bool,
+) -> Vec<(String, BlcClient)> {
+    if !all {
+        let client = session::token(credentials).and_then(|token| {
+            session::builder(explicit_api, credentials, settings, network)?
+                .token(token)
+                .build()
+        });
+        return client
+            .map(|client| vec![(credentials.profile().to_string(), client)])
+            .unwrap_or_default();
+    }
+
+    config::saved_profiles()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|name| {
+            let cfg = credentials.load_profile(&name).ok().flatten()?;
+            let endpoint = Some(cfg.endpoint.clone())
+                .filter(|endpoint| !endpoint.is_empty())
+                .or_else(|| settings.profile_api(&name).map(str::to_string))?;
+            let client = session::builder(Some(&endpoint), credentials, settings, network)
+                .ok()?
+                .token(cfg.token)
+                .build()
+                .ok()?;
+            Some((name, client))
+        })
+        .collect()
+}
+
+/// Warn when the endpoint saved at login is used instead of a new value of the `api` key.
+fn warn_saved_endpoint(credentials: &Credentials, settings: &Settings, value: &str) {
+    // $BLC_TOKEN 不使用保存的地址; 加密存储也不为此询问口令
+    if config::env_token().is_some()
+        || (settings.credential_store == CredentialStore::Encrypted
+            && std::env::var_os("BLC_PASSPHRASE").is_none())
+    {
+        return;
+    }
+    let Ok(Some(cfg)) = credentials.load() else {
+        return;
+    };
+
+    let value = value.trim().trim_end_matches('/');
+    if !cfg.endpoint.is_empty() && cfg.endpoint != value {
+        eprintln!(
+            "{} Profile {} is logged in to {}, which takes precedence over `api`. Run `blc login --api {}` to switch.",
+            "!".yellow().bold(),
+            credentials.profile().bold(),
+            cfg.endpoint,
+            value
+        );
+    }
+}
+
+/// The login password from stdin, `$BLC_PASSWORD` or an interactive prompt, in that order.
+fn read_password(from_stdin: bool) -> Result<String> {
+    if from_stdin {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    match std::env::var("BLC_PASSWORD") {
+        Ok(password) => Ok(password),
+        Err(_) => rpassword::prompt_password("password: ").map_err(Error::from),
+    }
+}
+
+/// Global options that take a value, e.g. `--api <URL>`.
+fn global_value_flags() -> Vec<String> {
+    Cli::command()
+        .get_arguments()
+        .filter(|arg| arg.is_global_set() && arg.get_action().takes_values())
+        .flat_map(|arg| {
+            let long = arg.get_long().map(|long| format!("--{long}"));
+            let short = arg.get_short().map(|short| format!("-{short}"));
+            long.into_iter().chain(short)
+        })
+        .collect()
+}
+
+/// Log the requests of this run to standard error at the level chosen by `-v`/`--verbose`.
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => return,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+
+    // 只记录本程序的日志, 不包括 hyper 等依赖
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_max_level(level)
+        .with_target(false)
+        .without_time()
+        .finish()
+        .with(Targets::new().with_target("blc", level))
+        .init();
+}
+
+/// Authenticated client for the current profile.
+fn connect(
+    explicit_api: Option<&str>,
+    credentials: &Credentials,
+    settings: &Settings,
+    network: &NetworkArgs,
+) -> BlcClient {
+    session::client(explicit_api, credentials, settings, network).unwrap_or_else(on_error)
+}
+
+/// Exit on an error, keeping the data already given to `printer` in structured output.
+fn fail<W: Write, R>(printer: Printer<W>, e: Error) -> R {
+    let _ = printer.error(&e);
+    std::process::exit(e.exit_code())
+}
+
 fn on_error<R>(e: Error) -> R {
-    eprintln!("{} Error: {}", "×".red().bold(), e);
-    std::process::exit(1)
+    let format = FORMAT.get().copied().unwrap_or_default();
+    let _ = Printer::new(format, std::io::stdout()).error(&e);
+    std::process::exit(e.exit_code())
 }
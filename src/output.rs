@@ -0,0 +1,223 @@
+//! Rendering of command results as colored text or as JSON, YAML and NDJSON documents.
+
+use std::fmt::Display;
+use std::io::Write;
+
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human readable, colored when writing to a terminal.
+    #[default]
+    Text,
+
+    /// A single JSON document with the status, message and data.
+    Json,
+
+    /// The same document as `json`, in YAML.
+    Yaml,
+
+    /// One JSON object per line, one line per item of a listing.
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// Whether the output is meant to be read by programs.
+    pub fn is_structured(self) -> bool {
+        self != OutputFormat::Text
+    }
+}
+
+/// Writes the result of a command in the chosen format.
+///
+/// Text is written as it comes. For `json` and `yaml` the data is kept until
+/// [`Printer::finish`] writes it together with the status, so that the output is one document.
+pub struct Printer<W: Write> {
+    format: OutputFormat,
+    out: W,
+    data: Option<Value>,
+
+    // 是否已按 ndjson 逐行输出过数据
+    streamed: bool,
+}
+
+impl<W: Write> Printer<W> {
+    pub fn new(format: OutputFormat, out: W) -> Self {
+        Printer {
+            format,
+            out,
+            data: None,
+            streamed: false,
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// A single object, e.g. a category.
+    pub fn record<T: Serialize + Display>(&mut self, value: &T) -> Result<()> {
+        match self.format {
+            OutputFormat::Text => writeln!(self.out, "{}", value)?,
+            OutputFormat::Ndjson => {
+                self.line(&to_value(value)?)?;
+                self.streamed = true;
+            }
+            OutputFormat::Json | OutputFormat::Yaml => self.data = Some(to_value(value)?),
+        }
+        Ok(())
+    }
+
+    /// A page of results, written one line per item in `ndjson`.
+    pub fn listing<T, I>(&mut self, value: &T, items: &[I]) -> Result<()>
+    where
+        T: Serialize + Display,
+        I: Serialize,
+    {
+        match self.format {
+            OutputFormat::Ndjson => {
+                for item in items {
+                    self.line(&to_value(item)?)?;
+                    self.streamed = true;
+                }
+                Ok(())
+            }
+            _ => self.record(value),
+        }
+    }
+
+    /// Data for programs, not written in text output where notes describe it instead.
+    pub fn data<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::Ndjson => {
+                self.line(&to_value(value)?)?;
+                self.streamed = true;
+            }
+            OutputFormat::Json | OutputFormat::Yaml => self.data = Some(to_value(value)?),
+        }
+        Ok(())
+    }
+
+    /// A remark for people, only written in text output.
+    pub fn note(&mut self, text: &str) -> Result<()> {
+        if !self.format.is_structured() {
+            writeln!(self.out, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    /// Write the closing status of a successful command.
+    pub fn finish(mut self, message: Option<&str>) -> Result<()> {
+        match self.format {
+            OutputFormat::Text => {
+                if let Some(message) = message {
+                    writeln!(self.out, "{} Finished: {}", "✓".green().bold(), message)?;
+                }
+                Ok(())
+            }
+            // 已逐行输出数据时不再追加状态行, 空列表则只有状态行
+            OutputFormat::Ndjson if self.streamed => Ok(()),
+            _ => {
+                let document = json!({
+                    "ok": true,
+                    "message": message,
+                    "data": self.data.take(),
+                });
+                self.document(&document)
+            }
+        }
+    }
+
+    /// Write a failed command as an error object. Text output goes to stderr instead.
+    ///
+    /// The object has the `kind` of the error, the response `code` of the server if there is
+    /// one, the `message` and the `exit_code` of the process. Data recorded before is kept.
+    pub fn error(mut self, e: &Error) -> Result<()> {
+        if !self.format.is_structured() {
+            eprintln!("{} Error: {}", "×".red().bold(), e);
+            return Ok(());
+        }
+
+        let (code, message) = match e {
+            Error::ResponseError { code, message } => (Some(code.as_str()), message.clone()),
+            _ => (None, e.to_string()),
+        };
+        let mut document = json!({
+            "ok": false,
+            "error": {
+                "kind": e.kind().as_str(),
+                "code": code,
+                "message": message,
+                "exit_code": e.exit_code(),
+            },
+        });
+        // 失败前已有的数据一并输出, 如 status 的报告
+        if let Some(data) = self.data.take() {
+            document["data"] = data;
+        }
+        self.document(&document)
+    }
+
+    fn document(&mut self, value: &Value) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut self.out, value).map_err(to_io)?;
+                writeln!(self.out)?;
+            }
+            OutputFormat::Yaml => {
+                serde_yaml::to_writer(&mut self.out, value)
+                    .map_err(|e| std::io::Error::other(e.to_string()))?;
+            }
+            OutputFormat::Text | OutputFormat::Ndjson => self.line(value)?,
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, value: &Value) -> Result<()> {
+        serde_json::to_writer(&mut self.out, value).map_err(to_io)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+}
+
+/// Data with its own rendering for text output, e.g. a table built by the caller.
+pub struct Described<'a, T> {
+    text: String,
+    value: &'a T,
+}
+
+impl<'a, T: Serialize> Described<'a, T> {
+    pub fn new(text: String, value: &'a T) -> Self {
+        Described { text, value }
+    }
+}
+
+impl<T> Display for Described<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl<T: Serialize> Serialize for Described<'_, T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| to_io(e).into())
+}
+
+fn to_io(e: serde_json::Error) -> std::io::Error {
+    std::io::Error::other(e)
+}
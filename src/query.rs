@@ -0,0 +1,408 @@
+//! Filter expressions over articles for `--where`, e.g.
+//! `category=rust and tag:async and not visible and created>2024-01-01`.
+//!
+//! ```text
+//! expr       = and ("or" and)*
+//! and        = not ("and" not)*
+//! not        = "not" not | "(" expr ")" | "visible" | "tag:" VALUE | FIELD OP VALUE
+//! FIELD      = uri | category | tag | version | created
+//! OP         = "=" | "!=" | "<" | "<=" | ">" | ">=" | "~"  (contains)
+//! ```
+//!
+//! Values may be quoted with `"` or `'`. `created` is compared as a date, `YYYY-MM-DD` or
+//! `YYYY-MM-DD HH:MM:SS` in UTC, or an RFC 3339 timestamp like `2024-01-01T10:00:00+08:00`. Conditions the server can apply, the category and required
+//! tags, are sent along with the request, see [`Query::category`] and [`Query::tags`];
+//! the whole expression is still checked on every article.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::api::response::ArticleMetadata;
+use crate::error::{Error, Result};
+
+/// A parsed `--where` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Visible,
+    Compare(Field, Op, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Uri,
+    Category,
+    Tag,
+    Version,
+    Created,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+
+    /// Milliseconds since the epoch, like the timestamps of the API.
+    Time(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    Colon,
+    Open,
+    Close,
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {}", describe(token))));
+        }
+        Ok(Query { expr })
+    }
+
+    /// Whether `article` satisfies the expression.
+    pub fn matches(&self, article: &ArticleMetadata) -> bool {
+        self.expr.matches(article)
+    }
+
+    /// The category every match must be in, if the expression requires one.
+    pub fn category(&self) -> Option<&str> {
+        self.required()
+            .into_iter()
+            .find_map(|(field, value)| (field == Field::Category).then_some(value))
+    }
+
+    /// The tags every match must have.
+    pub fn tags(&self) -> Vec<&str> {
+        self.required()
+            .into_iter()
+            .filter_map(|(field, value)| (field == Field::Tag).then_some(value))
+            .collect()
+    }
+
+    /// Equality conditions joined by `and` at the top level, which every match must satisfy.
+    fn required(&self) -> Vec<(Field, &str)> {
+        let mut conditions = Vec::new();
+        let mut stack = vec![&self.expr];
+
+        while let Some(expr) = stack.pop() {
+            match expr {
+                Expr::And(left, right) => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+                Expr::Compare(field, Op::Eq, Value::Text(value)) => {
+                    conditions.push((*field, value.as_str()))
+                }
+                _ => {}
+            }
+        }
+        conditions
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Query::parse(source)
+    }
+}
+
+impl Expr {
+    fn matches(&self, article: &ArticleMetadata) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(article) && right.matches(article),
+            Expr::Or(left, right) => left.matches(article) || right.matches(article),
+            Expr::Not(expr) => !expr.matches(article),
+            Expr::Visible => article.visibility,
+            // 对于 != , 要求没有任何标签等于该值
+            Expr::Compare(Field::Tag, Op::Ne, Value::Text(value)) => {
+                article.tags.iter().all(|tag| tag != value)
+            }
+            Expr::Compare(Field::Tag, op, Value::Text(value)) => {
+                article.tags.iter().any(|tag| op.text(tag, value))
+            }
+            Expr::Compare(field, op, Value::Text(value)) => {
+                let actual = match field {
+                    Field::Uri => &article.uri,
+                    Field::Category => &article.category.slug,
+                    Field::Version => &article.version,
+                    Field::Tag | Field::Created => return false,
+                };
+                op.text(actual, value)
+            }
+            Expr::Compare(_, op, Value::Time(time)) => op.ordering(article.created_at.cmp(time)),
+        }
+    }
+}
+
+impl Op {
+    fn text(self, actual: &str, expected: &str) -> bool {
+        match self {
+            Op::Contains => actual.contains(expected),
+            op => op.ordering(actual.cmp(expected)),
+        }
+    }
+
+    fn ordering(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+
+        match self {
+            Op::Eq => ordering == Equal,
+            Op::Ne => ordering != Equal,
+            Op::Lt => ordering == Less,
+            Op::Le => ordering != Greater,
+            Op::Gt => ordering == Greater,
+            Op::Ge => ordering != Less,
+            Op::Contains => false,
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "~",
+        })
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found =
+            matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    Some(token) => {
+                        Err(invalid(format!("expected `)`, found {}", describe(&token))))
+                    }
+                    None => Err(invalid("missing `)`")),
+                }
+            }
+            Some(Token::Word(word)) => self.condition(&word),
+            Some(token) => Err(invalid(format!(
+                "expected a condition, found {}",
+                describe(&token)
+            ))),
+            None => Err(invalid("expected a condition, found the end")),
+        }
+    }
+
+    fn condition(&mut self, word: &str) -> Result<Expr> {
+        let field = match word.to_lowercase().as_str() {
+            "visible" => return Ok(Expr::Visible),
+            "uri" => Field::Uri,
+            "category" => Field::Category,
+            "tag" | "tags" => Field::Tag,
+            "version" => Field::Version,
+            "created" => Field::Created,
+            _ => {
+                return Err(invalid(format!(
+                    "unknown field `{word}`, available fields: uri, category, tag, version, created, visible"
+                )))
+            }
+        };
+
+        let op = match self.next() {
+            Some(Token::Colon) if field == Field::Tag => Op::Eq,
+            Some(Token::Op(op)) => op,
+            _ => return Err(invalid(format!("expected an operator after `{word}`"))),
+        };
+
+        let value = match self.next() {
+            Some(Token::Word(value) | Token::Quoted(value)) => value,
+            _ => return Err(invalid(format!("expected a value after `{word}{op}`"))),
+        };
+
+        let value = match field {
+            Field::Created if op == Op::Contains => {
+                return Err(invalid("`~` does not apply to `created`"))
+            }
+            Field::Created => Value::Time(parse_time(&value)?),
+            _ => Value::Text(value),
+        };
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | ':' | '~' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    ':' => Token::Colon,
+                    _ => Token::Op(Op::Contains),
+                });
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Op(match (c, equals) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(invalid("expected `!=`")),
+                }));
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(other) => value.push(other),
+                        None => return Err(invalid("unclosed quote")),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            _ => {
+                // 运算符之后的值只在空白或 `)` 处结束, 如 2024-01-01T10:00:00+08:00
+                let value = matches!(tokens.last(), Some(Token::Op(_) | Token::Colon));
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| {
+                    !c.is_whitespace()
+                        && if value {
+                            *c != ')'
+                        } else {
+                            !"()=!<>~:\"'".contains(*c)
+                        }
+                }) {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// A date or date and time in UTC, or an RFC 3339 timestamp, as milliseconds since the epoch.
+fn parse_time(value: &str) -> Result<i64> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp_millis());
+    }
+
+    let time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| {
+            invalid(format!(
+                "invalid date `{value}`, expected YYYY-MM-DD, YYYY-MM-DD HH:MM:SS or RFC 3339"
+            ))
+        })?;
+    Ok(time.and_utc().timestamp_millis())
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("`{word}`"),
+        Token::Quoted(value) => format!("\"{value}\""),
+        Token::Op(op) => format!("`{op}`"),
+        Token::Colon => "`:`".to_string(),
+        Token::Open => "`(`".to_string(),
+        Token::Close => "`)`".to_string(),
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidQuery(reason.into())
+}
@@ -0,0 +1,327 @@
+//! Full-text search over the URIs, tags, titles and version notes of articles for `blc search`.
+//!
+//! The version lists are fetched concurrently and cached per article in `~/.blc/cache`,
+//! keyed by the current version of the article, readable only by the owner. Runs with
+//! `$BLC_TOKEN` do not use the default cache, so that they leave `~/.blc` untouched. `rm`, `upload` and `delete` drop the entry
+//! of the article, since they change its versions without always changing the current one.
+
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+
+use colored::Colorize;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::api::response::{ArticleMetadata, Versions};
+use crate::api::BlogBackend;
+use crate::config;
+use crate::error::Result;
+
+/// How many version lists are requested at the same time.
+const CONCURRENCY: usize = 8;
+
+const URI_WEIGHT: u32 = 3;
+const TAG_WEIGHT: u32 = 3;
+const TITLE_WEIGHT: u32 = 4;
+const NOTE_WEIGHT: u32 = 1;
+
+/// Version lists saved on disk, one file per article.
+pub struct VersionCache {
+    dir: PathBuf,
+
+    // 为 false 时只写入缓存, 不读取
+    read: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CacheEntry {
+    /// The current version of the article when the list was fetched.
+    version: String,
+    versions: Versions,
+}
+
+impl VersionCache {
+    pub fn new(dir: PathBuf) -> Self {
+        VersionCache { dir, read: true }
+    }
+
+    /// Ignore the cached lists, replacing them with the ones fetched now.
+    pub fn refresh(mut self) -> Self {
+        self.read = false;
+        self
+    }
+
+    /// The cached versions of `article`, unless its current version changed since.
+    pub fn get(&self, article: &ArticleMetadata) -> Option<Versions> {
+        if !self.read {
+            return None;
+        }
+        let bytes = fs::read(self.path(&article.uri)).ok()?;
+        // 缓存损坏时当作未命中, 重新获取即可
+        let entry = serde_json::from_slice::<CacheEntry>(&bytes).ok()?;
+        (entry.version == article.version).then_some(entry.versions)
+    }
+
+    pub fn put(&self, article: &ArticleMetadata, versions: Versions) -> Result<Versions> {
+        let entry = CacheEntry {
+            version: article.version.clone(),
+            versions,
+        };
+        // 版本的标题和说明可能是未公开的草稿
+        config::create_private_dir(&self.dir)?;
+        config::write_private(
+            &self.path(&article.uri),
+            &serde_json::to_vec(&entry).map_err(std::io::Error::other)?,
+        )?;
+        Ok(entry.versions)
+    }
+
+    /// Drop the cached versions of the article at `uri`, if there are any.
+    pub fn remove(&self, uri: &str) -> Result<()> {
+        match fs::remove_file(self.path(uri)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, uri: &str) -> PathBuf {
+        let name = uri
+            .bytes()
+            .map(|b| match b {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => {
+                    (b as char).to_string()
+                }
+                b => format!("%{b:02X}"),
+            })
+            .collect::<String>();
+        self.dir.join(format!("{name}.json"))
+    }
+}
+
+/// Fetch the version lists of `articles` in the same order, concurrently and through `cache`.
+pub async fn versions<B: BlogBackend>(
+    backend: &B,
+    articles: &[ArticleMetadata],
+    cache: Option<&VersionCache>,
+) -> Result<Vec<Versions>> {
+    stream::iter(articles)
+        .map(|article| async move {
+            if let Some(versions) = cache.and_then(|cache| cache.get(article)) {
+                return Ok(versions);
+            }
+
+            let versions = backend
+                .get_article_version_list(&article.uri)
+                .await?
+                .into_data()
+                .unwrap_or(Versions {
+                    count: 0,
+                    items: Vec::new(),
+                });
+            match cache {
+                Some(cache) => cache.put(article, versions),
+                None => Ok(versions),
+            }
+        })
+        .buffered(CONCURRENCY)
+        .try_collect()
+        .await
+}
+
+/// The words to look for. An article matches when it contains all of them, ignoring case.
+pub struct Search {
+    terms: Vec<String>,
+}
+
+/// A matching article and where the words were found.
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub uri: String,
+    pub score: u32,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub version: String,
+    pub matches: Vec<Match>,
+
+    #[serde(skip)]
+    terms: Vec<String>,
+}
+
+/// A title or note containing at least one of the words.
+#[derive(Debug, Serialize)]
+pub struct Match {
+    pub field: &'static str,
+    pub version: String,
+    pub text: String,
+}
+
+/// All hits, best first.
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub count: usize,
+    pub items: Vec<SearchHit>,
+}
+
+impl Search {
+    pub fn new(query: &str) -> Self {
+        Search {
+            terms: query.split_whitespace().map(str::to_lowercase).collect(),
+        }
+    }
+
+    /// Score every article, keeping those that contain all the words, best first.
+    ///
+    /// A word counts once per field it appears in, titles weighing the most.
+    pub fn rank(&self, articles: Vec<ArticleMetadata>, versions: Vec<Versions>) -> SearchResults {
+        let mut items = articles
+            .into_iter()
+            .zip(versions)
+            .filter_map(|(article, versions)| self.score(article, versions))
+            .collect::<Vec<_>>();
+
+        // 稳定排序, 同分时保持服务端的顺序(最新的在前)
+        items.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+        SearchResults {
+            count: items.len(),
+            items,
+        }
+    }
+
+    fn score(&self, article: ArticleMetadata, versions: Versions) -> Option<SearchHit> {
+        if self.terms.is_empty() {
+            return None;
+        }
+
+        let mut matches = Vec::new();
+        for version in versions.items {
+            for (field, text) in [("title", version.title), ("note", version.note)] {
+                if self.terms.iter().any(|term| contains(&text, term)) {
+                    matches.push(Match {
+                        field,
+                        version: version.version.clone(),
+                        text,
+                    });
+                }
+            }
+        }
+
+        let mut score = 0;
+        for term in &self.terms {
+            let mut found = 0;
+            if contains(&article.uri, term) {
+                found += URI_WEIGHT;
+            }
+            if article.tags.iter().any(|tag| contains(tag, term)) {
+                found += TAG_WEIGHT;
+            }
+            if matches
+                .iter()
+                .any(|m| m.field == "title" && contains(&m.text, term))
+            {
+                found += TITLE_WEIGHT;
+            }
+            if matches
+                .iter()
+                .any(|m| m.field == "note" && contains(&m.text, term))
+            {
+                found += NOTE_WEIGHT;
+            }
+
+            if found == 0 {
+                return None;
+            }
+            score += found;
+        }
+
+        Some(SearchHit {
+            uri: article.uri,
+            score,
+            category: article.category.slug,
+            tags: article.tags,
+            version: article.version,
+            matches,
+            terms: self.terms.clone(),
+        })
+    }
+}
+
+impl Display for SearchHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            highlight(&self.uri, &self.terms).bold(),
+            self.category,
+            self.tags
+                .iter()
+                .map(|tag| highlight(tag, &self.terms))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+
+        for m in &self.matches {
+            let current = if m.version == self.version { "*" } else { " " };
+            write!(
+                f,
+                "\n  {current}{} {:<5}  {}",
+                m.version,
+                m.field,
+                highlight(&m.text, &self.terms)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SearchResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hits = self
+            .items
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", hits.join("\n\n"))
+    }
+}
+
+fn contains(text: &str, term: &str) -> bool {
+    text.to_lowercase().contains(term)
+}
+
+/// Mark every occurrence of the words in `text`, ignoring case.
+fn highlight(text: &str, terms: &[String]) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut marked = vec![false; chars.len()];
+
+    for term in terms {
+        let length = term.chars().count();
+        if length == 0 {
+            continue;
+        }
+        for start in 0..chars.len() {
+            let end = start + length;
+            if end <= chars.len()
+                && chars[start..end].iter().collect::<String>().to_lowercase() == *term
+            {
+                marked[start..end].iter_mut().for_each(|m| *m = true);
+            }
+        }
+    }
+
+    let mut result = String::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = (start..chars.len())
+            .find(|&i| marked[i] != marked[start])
+            .unwrap_or(chars.len());
+        let run = chars[start..end].iter().collect::<String>();
+        if marked[start] {
+            result.push_str(&run.yellow().bold().to_string());
+        } else {
+            result.push_str(&run);
+        }
+        start = end;
+    }
+    result
+}
@@ -0,0 +1,199 @@
+use std::io::IsTerminal;
+use std::sync::Once;
+use std::time::Duration;
+
+use colored::Colorize;
+
+use crate::api::{BlcClient, BlcClientBuilder, Relogin, RetryPolicy, Session};
+use crate::cli::NetworkArgs;
+use crate::config::{self, Credentials};
+use crate::error::{Error, Result};
+use crate::settings::Settings;
+
+/// Endpoint baked in at compile time, only used when nothing else is configured.
+const DEFAULT_BASE_URL: Option<&str> = option_env!("BLC_API");
+
+/// The API endpoint for this run.
+///
+/// The explicit value (`--api`, `$BLC_API` or the profile endpoint) wins over the
+/// endpoint saved at login, then the `api` configuration key, then the compile-time default.
+/// The saved endpoint goes before the `api` key because the saved token is only valid there.
+pub fn endpoint(
+    explicit: Option<&str>,
+    credentials: &Credentials,
+    settings: &Settings,
+) -> Result<String> {
+    let mut corrupt = None;
+    let url = match explicit {
+        Some(url) => Some(url.to_string()),
+        // 使用$BLC_TOKEN时不读取也不改写本地登录信息
+        None if config::env_token().is_some() => None,
+        // 登录信息损坏时仍允许通过其他途径得到地址并重新登录
+        None => match credentials.load() {
+            Ok(cfg) => cfg.map(|cfg| cfg.endpoint.clone()),
+            Err(e @ Error::CorruptCredentials { .. }) => {
+                corrupt = Some(e);
+                None
+            }
+            Err(e) => return Err(e),
+        }
+        .filter(|e| !e.is_empty()),
+    }
+    .or_else(|| settings.api.clone())
+    .or_else(|| DEFAULT_BASE_URL.map(str::to_string))
+    .map(|url| url.trim().trim_end_matches('/').to_string())
+    .filter(|url| !url.is_empty());
+
+    url.ok_or(corrupt.unwrap_or(Error::NoEndpoint))
+}
+
+/// The token for this run, from `$BLC_TOKEN` or the saved login state.
+pub fn token(credentials: &Credentials) -> Result<String> {
+    if let Some(token) = config::env_token() {
+        return Ok(token);
+    }
+
+    let cfg = credentials.load()?.ok_or(Error::UnAuth)?;
+    cfg.warn_expiry();
+    Ok(cfg.token.clone())
+}
+
+/// Client builder with the endpoint, timeouts and retries from the command line
+/// and the configuration.
+///
+/// `flags` are the network options of the command line, they override the configuration.
+pub fn builder(
+    explicit: Option<&str>,
+    credentials: &Credentials,
+    settings: &Settings,
+    flags: &NetworkArgs,
+) -> Result<BlcClientBuilder> {
+    let mut builder = BlcClient::builder()
+        .endpoint(endpoint(explicit, credentials, settings)?)
+        .retry(retry_policy(flags, settings));
+
+    if let Some(secs) = flags.timeout.or(settings.timeout) {
+        builder = builder.timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = flags.connect_timeout.or(settings.connect_timeout) {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = flags.read_timeout.or(settings.read_timeout) {
+        builder = builder.read_timeout(Duration::from_secs(secs));
+    }
+
+    if let Some(url) = flags.proxy.as_ref().or(settings.proxy.as_ref()) {
+        builder = builder.proxy(url);
+    }
+    if let Some(hosts) = &settings.no_proxy {
+        builder = builder.no_proxy(hosts);
+    }
+    if let Some(path) = flags.ca_cert.as_ref().or(settings.ca_cert.as_ref()) {
+        builder = builder.root_certificate(path);
+    }
+    // 证书和私钥必须成对来自同一处, 不混用命令行和配置
+    let identity = match (&flags.client_cert, &flags.client_key) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        _ => match (&settings.client_cert, &settings.client_key) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            (Some(path), None) | (None, Some(path)) => {
+                return Err(Error::InvalidCertificate {
+                    path: path.clone(),
+                    reason: "`client_cert` and `client_key` must be set together".to_string(),
+                })
+            }
+            (None, None) => None,
+        },
+    };
+    if let Some((cert, key)) = identity {
+        builder = builder.identity(cert, key);
+    }
+    if flags.insecure || settings.insecure {
+        static WARNING: Once = Once::new();
+        WARNING.call_once(|| {
+            eprintln!(
+                "{} TLS certificate verification is disabled (--insecure).",
+                "!".yellow().bold()
+            )
+        });
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    Ok(builder)
+}
+
+fn retry_policy(flags: &NetworkArgs, settings: &Settings) -> RetryPolicy {
+    let mut policy = RetryPolicy::default();
+
+    if let Some(retries) = flags.retries.or(settings.retries) {
+        policy.max_retries = retries;
+    }
+    if let Some(ms) = flags.retry_delay_ms.or(settings.retry_delay_ms) {
+        policy.base_delay = Duration::from_millis(ms);
+    }
+    if let Some(ms) = flags.retry_max_delay_ms.or(settings.retry_max_delay_ms) {
+        policy.max_delay = Duration::from_millis(ms);
+    }
+    policy.retry_post = flags.retry_post || settings.retry_post;
+
+    policy
+}
+
+/// Client for logging in, without a token.
+pub fn anonymous_client(
+    explicit: Option<&str>,
+    credentials: &Credentials,
+    settings: &Settings,
+    flags: &NetworkArgs,
+) -> Result<BlcClient> {
+    builder(explicit, credentials, settings, flags)?.build()
+}
+
+/// Client authenticated with the token of the current profile.
+///
+/// A rejected token is renewed by prompting for the password, unless it came from `$BLC_TOKEN`.
+pub fn client(
+    explicit: Option<&str>,
+    credentials: &Credentials,
+    settings: &Settings,
+    flags: &NetworkArgs,
+) -> Result<BlcClient> {
+    let endpoint = endpoint(explicit, credentials, settings)?;
+    let builder =
+        builder(Some(&endpoint), credentials, settings, flags)?.token(token(credentials)?);
+
+    if config::env_token().is_some() {
+        builder.build()
+    } else {
+        builder
+            .relogin(PromptRelogin {
+                endpoint,
+                credentials: credentials.clone(),
+            })
+            .build()
+    }
+}
+
+/// Asks for the password at the terminal and saves the new token.
+struct PromptRelogin {
+    endpoint: String,
+    credentials: Credentials,
+}
+
+impl Relogin for PromptRelogin {
+    fn password(&self) -> Option<String> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+
+        eprintln!(
+            "{} The server rejected the login token, please log in again.",
+            "!".yellow().bold()
+        );
+        rpassword::prompt_password("password: ").ok()
+    }
+
+    fn renewed(&self, session: &Session) -> Result<()> {
+        self.credentials.save_session(session, &self.endpoint)
+    }
+}
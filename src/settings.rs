@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use config::{ConfigError, Environment, File, FileFormat, Value, ValueKind};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::output::OutputFormat;
+
+/// Name of the per-project configuration file, searched upward from the working directory.
+pub const PROJECT_FILE_NAME: &str = "blc.toml";
+
+/// Scalar keys that may be read from files and `BLC_*` environment variables.
+const KEYS: &[&str] = &[
+    "api",
+    "default_category",
+    "page_limit",
+    "output",
+    "color",
+    "timeout",
+    "connect_timeout",
+    "read_timeout",
+    "retries",
+    "retry_delay_ms",
+    "retry_max_delay_ms",
+    "retry_post",
+    "proxy",
+    "no_proxy",
+    "ca_cert",
+    "client_cert",
+    "client_key",
+    "insecure",
+    "profile",
+    "credential_store",
+    "token_lifetime",
+    "cache_dir",
+];
+
+/// Table keys whose entries are free-form, e.g. `aliases.ls` or `profiles.work.api`.
+const TABLE_KEYS: &[&str] = &["aliases", "profiles", "templates"];
+
+/// User preferences merged from, in increasing precedence:
+/// built-in defaults, `~/.config/blc/config.toml`, the nearest `blc.toml`
+/// and `BLC_*` environment variables. Command line flags override all of them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Base URL of the BlogLite API.
+    pub api: Option<String>,
+
+    /// Category used by `blc init` when `--category` is omitted.
+    pub default_category: Option<String>,
+
+    /// Number of articles per page used by `blc list` when `--limit` is omitted.
+    pub page_limit: Option<u32>,
+
+    /// Output format, `text`, `json`, `yaml` or `ndjson`.
+    pub output: OutputFormat,
+
+    /// Whether to colorize the output.
+    pub color: ColorMode,
+
+    /// Total request timeout in seconds.
+    pub timeout: Option<u64>,
+
+    /// Connection timeout in seconds.
+    pub connect_timeout: Option<u64>,
+
+    /// Timeout of each read from the connection in seconds.
+    pub read_timeout: Option<u64>,
+
+    /// How often a failed request is retried, 0 disables retrying.
+    pub retries: Option<u32>,
+
+    /// Delay before the first retry in milliseconds, doubled for every further retry.
+    pub retry_delay_ms: Option<u64>,
+
+    /// Upper bound of the delay between retries in milliseconds.
+    pub retry_max_delay_ms: Option<u64>,
+
+    /// Also retry POST requests, which may not be idempotent.
+    pub retry_post: bool,
+
+    /// Proxy for all requests, e.g. `http://proxy.example.com:3128`.
+    pub proxy: Option<String>,
+
+    /// Hosts reached without the proxy, comma separated.
+    pub no_proxy: Option<String>,
+
+    /// PEM file with additional trusted root certificates.
+    pub ca_cert: Option<PathBuf>,
+
+    /// PEM file with the client certificate chain for mutual TLS.
+    pub client_cert: Option<PathBuf>,
+
+    /// PEM file with the PKCS #8 private key of `client_cert`.
+    pub client_key: Option<PathBuf>,
+
+    /// Skip verifying the TLS certificate of the server.
+    pub insecure: bool,
+
+    /// Command aliases, e.g. `ls = "list --limit 50"`.
+    pub aliases: HashMap<String, String>,
+
+    /// Named templates for `--format`, e.g. `index = "- [{uri}](/{uri})"`.
+    pub templates: HashMap<String, String>,
+
+    /// Profile used when `--profile` is not given.
+    pub profile: Option<String>,
+
+    /// Named profiles, e.g. `[profiles.work]`.
+    pub profiles: HashMap<String, ProfileSettings>,
+
+    /// How login credentials are written to disk.
+    pub credential_store: CredentialStore,
+
+    /// Token lifetime in seconds, used when the server does not report one.
+    pub token_lifetime: Option<u64>,
+
+    /// Where `blc search` caches version lists, defaults to `~/.blc/cache`.
+    pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialStore {
+    /// Owner-only file, the token is merely obfuscated.
+    #[default]
+    Plain,
+
+    /// Encrypted with a key derived from a passphrase (age/scrypt).
+    Encrypted,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileSettings {
+    /// Base URL of the BlogLite API for this profile.
+    pub api: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn apply(self) {
+        match self {
+            ColorMode::Auto => {}
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+/// Where a configuration file is written by `blc config set`.
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    User,
+    Project,
+}
+
+impl Settings {
+    pub fn load() -> Result<Self> {
+        Settings::layers()?.try_deserialize().map_err(Error::from)
+    }
+
+    /// The defaults with only the aliases of the configuration, for when another value is invalid.
+    pub fn aliases_only() -> Self {
+        let aliases = Settings::layers()
+            .and_then(|layers| layers.get("aliases").map_err(Error::from))
+            .unwrap_or_default();
+
+        Settings {
+            aliases,
+            ..Default::default()
+        }
+    }
+
+    /// Build the merged configuration without deserializing it, keeping track of
+    /// where each value came from.
+    pub fn layers() -> Result<config::Config> {
+        let mut builder = config::Config::builder()
+            .set_default("output", "text")?
+            .set_default("color", "auto")?
+            .set_default("credential_store", "plain")?;
+
+        if let Some(path) = user_config_path() {
+            builder = builder.add_source(File::from(path).format(FileFormat::Toml).required(false));
+        }
+
+        if let Some(path) = find_project_config() {
+            builder = builder.add_source(File::from(path).format(FileFormat::Toml));
+        }
+
+        let vars = env::vars()
+            .filter(|(key, _)| {
+                key.strip_prefix("BLC_")
+                    .is_some_and(|key| KEYS.contains(&key.to_lowercase().as_str()))
+            })
+            .collect();
+
+        builder
+            .add_source(
+                Environment::with_prefix("BLC")
+                    .source(Some(vars))
+                    .try_parsing(true),
+            )
+            .build()
+            .map_err(Error::from)
+    }
+
+    /// All configured values as sorted `(key, value, origin)` triples.
+    pub fn entries() -> Result<Vec<(String, String, String)>> {
+        let table = Settings::layers()?.cache.into_table()?;
+
+        let mut entries = Vec::new();
+        flatten("", table, &mut entries);
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(entries)
+    }
+
+    /// Look up a single value and its origin.
+    pub fn get(key: &str) -> Result<(String, String)> {
+        check_key(key)?;
+
+        Settings::entries()?
+            .into_iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, value, origin)| (value, origin))
+            .ok_or_else(|| Error::UnsetConfigKey(key.to_string()))
+    }
+
+    /// Persist `key = value` into the configuration file of the given scope,
+    /// returning the path of the file that was written.
+    pub fn set(key: &str, value: &str, scope: Scope) -> Result<PathBuf> {
+        check_key(key)?;
+
+        let value = parse_value(value);
+
+        // 先用单独的表校验类型, 避免把无效值写进配置文件
+        let mut probe = toml::Table::new();
+        insert_dotted(&mut probe, key, value.clone());
+        probe
+            .try_into::<Settings>()
+            .map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+
+        let path = match scope {
+            Scope::User => user_config_path().ok_or(Error::NoHomeDir)?,
+            Scope::Project => {
+                find_project_config().unwrap_or_else(|| PathBuf::from(PROJECT_FILE_NAME))
+            }
+        };
+
+        let mut table = read_table(&path)?;
+        insert_dotted(&mut table, key, value);
+        write_table(&path, &table)?;
+
+        Ok(path)
+    }
+
+    /// Remove a key from the configuration file of the given scope.
+    ///
+    /// Returns the path of the file that was changed, or `None` if the key was not set there.
+    pub fn unset(key: &str, scope: Scope) -> Result<Option<PathBuf>> {
+        check_key(key)?;
+
+        let Some(path) = (match scope {
+            Scope::User => Some(user_config_path().ok_or(Error::NoHomeDir)?),
+            Scope::Project => find_project_config(),
+        }) else {
+            return Ok(None);
+        };
+
+        let mut table = read_table(&path)?;
+        if remove_dotted(&mut table, key).is_none() {
+            return Ok(None);
+        }
+        write_table(&path, &table)?;
+
+        Ok(Some(path))
+    }
+
+    /// The endpoint configured for a named profile.
+    pub fn profile_api(&self, name: &str) -> Option<&str> {
+        self.profiles.get(name)?.api.as_deref()
+    }
+
+    /// Expand a user-defined alias in place of the subcommand, if there is one.
+    ///
+    /// `global_value_flags` are global options that consume the next argument,
+    /// so that their values are not mistaken for the subcommand.
+    pub fn expand_aliases(&self, args: Vec<String>, global_value_flags: &[String]) -> Vec<String> {
+        let mut position = None;
+        let mut skip_next = false;
+
+        for (i, arg) in args.iter().enumerate().skip(1) {
+            if skip_next {
+                skip_next = false;
+            } else if arg.starts_with('-') {
+                skip_next = global_value_flags.contains(arg);
+            } else {
+                position = Some(i);
+                break;
+            }
+        }
+
+        let Some(i) = position else { return args };
+        let Some(expansion) = self.aliases.get(&args[i]) else {
+            return args;
+        };
+
+        let mut expanded = args[..i].to_vec();
+        expanded.extend(expansion.split_whitespace().map(str::to_string));
+        expanded.extend_from_slice(&args[i + 1..]);
+        expanded
+    }
+}
+
+/// `$XDG_CONFIG_HOME/blc/config.toml`, falling back to `~/.config/blc/config.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .or_else(|| env::var_os("USERPROFILE"))
+                .map(|home| Path::new(&home).join(".config"))
+        })?;
+
+    Some(dir.join("blc").join("config.toml"))
+}
+
+/// The nearest `blc.toml` in the working directory or one of its parents.
+pub fn find_project_config() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn check_key(key: &str) -> Result<()> {
+    let known = KEYS.contains(&key)
+        || key
+            .split_once('.')
+            .is_some_and(|(table, name)| TABLE_KEYS.contains(&table) && !name.is_empty());
+
+    if known {
+        Ok(())
+    } else {
+        Err(Error::UnknownConfigKey(key.to_string()))
+    }
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    if let Ok(n) = value.parse::<i64>() {
+        toml::Value::Integer(n)
+    } else if let Ok(b) = value.parse::<bool>() {
+        toml::Value::Boolean(b)
+    } else {
+        toml::Value::String(value.to_string())
+    }
+}
+
+fn insert_dotted(table: &mut toml::Table, key: &str, value: toml::Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let entry = table
+                .entry(head)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            if let toml::Value::Table(inner) = entry {
+                insert_dotted(inner, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn remove_dotted(table: &mut toml::Table, key: &str) -> Option<toml::Value> {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let toml::Value::Table(inner) = table.get_mut(head)? else {
+                return None;
+            };
+            let removed = remove_dotted(inner, rest);
+            if inner.is_empty() {
+                table.remove(head);
+            }
+            removed
+        }
+        None => table.remove(key),
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+
+    fs::read_to_string(path)?
+        .parse::<toml::Table>()
+        .map_err(|e| Error::Config(ConfigError::Foreign(Box::new(e))))
+}
+
+fn write_table(path: &Path, table: &toml::Table) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+
+    let content = toml::to_string_pretty(table)
+        .map_err(|e| Error::Config(ConfigError::Foreign(Box::new(e))))?;
+    fs::write(path, content).map_err(Error::from)
+}
+
+fn flatten(
+    prefix: &str,
+    table: config::Map<String, Value>,
+    out: &mut Vec<(String, String, String)>,
+) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        let origin = match value.origin() {
+            Some("the environment") => "env".to_string(),
+            Some(origin) => format!("file:{origin}"),
+            None => "default".to_string(),
+        };
+
+        match value.kind {
+            ValueKind::Table(inner) => flatten(&key, inner, out),
+            kind => out.push((key, kind.to_string(), origin)),
+        }
+    }
+}
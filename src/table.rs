@@ -0,0 +1,273 @@
+//! Aligned text tables for `list` and `versions`.
+
+use std::env;
+use std::io::IsTerminal;
+
+use colored::Colorize;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::api::response::{format_detatime_from_timestamp, ArticleMetadata, Version};
+use crate::error::{Error, Result};
+use crate::TableArgs;
+
+const SEPARATOR: &str = "  ";
+
+/// Columns are not truncated below this width, so that they stay recognizable.
+const MIN_WIDTH: usize = 8;
+
+/// A column of a table over rows of type `T`.
+pub struct Column<T> {
+    pub name: &'static str,
+    header: &'static str,
+
+    /// Whether the column may be truncated to fit the terminal.
+    shrink: bool,
+
+    value: fn(&T) -> String,
+}
+
+pub const ARTICLE_COLUMNS: &[Column<ArticleMetadata>] = &[
+    Column {
+        name: "visible",
+        header: "VIS",
+        shrink: false,
+        value: |article| if article.visibility { "*" } else { "" }.to_string(),
+    },
+    Column {
+        name: "uri",
+        header: "URI",
+        shrink: true,
+        value: |article| article.uri.clone(),
+    },
+    Column {
+        name: "category",
+        header: "CATEGORY",
+        shrink: true,
+        value: |article| article.category.slug.clone(),
+    },
+    Column {
+        name: "tags",
+        header: "TAGS",
+        shrink: true,
+        value: |article| article.tags.join(", "),
+    },
+    Column {
+        name: "version",
+        header: "VERSION",
+        shrink: false,
+        value: |article| article.version.clone(),
+    },
+    Column {
+        name: "created",
+        header: "CREATED",
+        shrink: false,
+        value: |article| format_detatime_from_timestamp(article.created_at),
+    },
+];
+
+pub const VERSION_COLUMNS: &[Column<Version>] = &[
+    Column {
+        name: "version",
+        header: "VERSION",
+        shrink: false,
+        value: |version| version.version.clone(),
+    },
+    Column {
+        name: "title",
+        header: "TITLE",
+        shrink: true,
+        value: |version| version.title.clone(),
+    },
+    Column {
+        name: "note",
+        header: "NOTE",
+        shrink: true,
+        value: |version| version.note.clone(),
+    },
+    Column {
+        name: "created",
+        header: "CREATED",
+        shrink: false,
+        value: |version| format_detatime_from_timestamp(version.created_at),
+    },
+];
+
+/// Sort `rows` in place as requested by `--sort` and `--reverse`.
+///
+/// Only the rows at hand are sorted, i.e. a single page of a listing.
+pub fn sort<T>(columns: &[Column<T>], rows: &mut [T], args: &TableArgs) -> Result<()> {
+    if let Some(name) = &args.sort {
+        let column = find(columns, name)?;
+        // 日期格式为 %Y-%m-%d %H:%M:%S, 按字符串排序即按时间排序
+        rows.sort_by_cached_key(|row| (column.value)(row));
+    }
+    if args.reverse {
+        rows.reverse();
+    }
+    Ok(())
+}
+
+/// Render `rows` with the columns chosen by `--columns`, or all of them.
+///
+/// Wide columns are truncated to fit `width`, see [`terminal_width`].
+pub fn render<T>(
+    columns: &[Column<T>],
+    rows: &[T],
+    args: &TableArgs,
+    width: Option<usize>,
+) -> Result<String> {
+    Ok(StreamedTable::new(columns, args, width)?.render(rows))
+}
+
+/// A table printed in parts as its rows arrive, e.g. one page at a time.
+///
+/// The widths of the columns are taken from the first part and only grow afterwards,
+/// so later parts line up with the earlier ones unless they hold wider values.
+pub struct StreamedTable<'a, T> {
+    columns: Vec<&'a Column<T>>,
+    widths: Vec<usize>,
+    header: bool,
+    width: Option<usize>,
+}
+
+impl<'a, T> StreamedTable<'a, T> {
+    /// A table with the columns chosen by `--columns`, fitted into `width`.
+    pub fn new(columns: &'a [Column<T>], args: &TableArgs, width: Option<usize>) -> Result<Self> {
+        let columns = match &args.columns {
+            Some(names) => names
+                .iter()
+                .map(|name| find(columns, name.trim()))
+                .collect::<Result<Vec<_>>>()?,
+            None => columns.iter().collect(),
+        };
+        let widths = columns
+            .iter()
+            .map(|column| {
+                if args.no_header {
+                    0
+                } else {
+                    column.header.width()
+                }
+            })
+            .collect();
+
+        Ok(StreamedTable {
+            columns,
+            widths,
+            header: !args.no_header,
+            width,
+        })
+    }
+
+    /// The lines of `rows`, preceded by the header in the first part.
+    pub fn render(&mut self, rows: &[T]) -> String {
+        let cells = rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|column| (column.value)(row))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut widths = self.widths.clone();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        if let Some(width) = self.width {
+            fit(&self.columns, &mut widths, width);
+        }
+        let mut lines = Vec::new();
+        if std::mem::take(&mut self.header) {
+            let headers = self
+                .columns
+                .iter()
+                .map(|column| column.header.to_string())
+                .collect::<Vec<_>>();
+            lines.push(line(&headers, &widths).bold().to_string());
+        }
+        lines.extend(cells.iter().map(|row| line(row, &widths)));
+
+        // 之后的部分从已输出的列宽开始, 以便对齐
+        self.widths = widths;
+        lines.join("\n")
+    }
+}
+
+/// Width of the terminal the output goes to, `$COLUMNS` taking precedence.
+///
+/// Returns `None` when writing to a pipe or file, where nothing is truncated.
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    terminal_size::terminal_size().map(|(width, _)| width.0 as usize)
+}
+
+fn find<'a, T>(columns: &'a [Column<T>], name: &str) -> Result<&'a Column<T>> {
+    columns
+        .iter()
+        .find(|column| column.name == name)
+        .ok_or_else(|| Error::UnknownColumn {
+            column: name.to_string(),
+            available: columns.iter().map(|column| column.name).collect(),
+        })
+}
+
+/// Shrink the widest shrinkable columns until the table fits into `width`.
+fn fit<T>(columns: &[&Column<T>], widths: &mut [usize], width: usize) {
+    let separators = SEPARATOR.len() * widths.len().saturating_sub(1);
+    let mut total = widths.iter().sum::<usize>() + separators;
+
+    while total > width {
+        let widest = (0..widths.len())
+            .filter(|&i| columns[i].shrink && widths[i] > MIN_WIDTH)
+            .max_by_key(|&i| widths[i]);
+        let Some(i) = widest else { break };
+
+        widths[i] -= 1;
+        total -= 1;
+    }
+}
+
+fn line(cells: &[String], widths: &[usize]) -> String {
+    let line = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, &width)| {
+            let cell = truncate(cell, width);
+            let padding = width.saturating_sub(cell.width());
+            format!("{cell}{}", " ".repeat(padding))
+        })
+        .collect::<Vec<_>>()
+        .join(SEPARATOR);
+
+    // 末尾的列可能为空, 不留行尾空白
+    line.trim_end().to_string()
+}
+
+/// Cut `text` to at most `width` columns, marking the cut with an ellipsis.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result.push('…');
+    result
+}
@@ -0,0 +1,347 @@
+//! User-defined line templates for `--format`, e.g. `{uri}\t{category.slug}\t{tags|join(",")}`.
+//!
+//! A template is literal text with `{field|filter|...}` placeholders. Fields are looked up in
+//! the [`Fields`] of each item, nested ones with dots. `{{` and `}}` stand for literal braces,
+//! and `\t`, `\n` and `\\` are unescaped so that templates can be given in single quotes.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use chrono::DateTime;
+use serde_json::{json, Value};
+
+use crate::api::response::{ArticleMetadata, CategoryItem, Version};
+use crate::error::{Error, Result};
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A parsed template, rendered once per item.
+#[derive(Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Field {
+        path: Vec<String>,
+        filters: Vec<Filter>,
+    },
+}
+
+#[derive(Debug)]
+enum Filter {
+    /// Milliseconds since the epoch formatted with a `strftime` pattern.
+    Date(String),
+
+    /// Array elements joined with a separator.
+    Join(String),
+
+    Upper,
+
+    Lower,
+
+    /// Replacement for a missing, null or empty value.
+    Default(String),
+}
+
+/// The values a template can refer to, named like the table columns.
+pub trait Fields {
+    fn fields(&self) -> Value;
+}
+
+impl Fields for ArticleMetadata {
+    fn fields(&self) -> Value {
+        json!({
+            "uri": self.uri,
+            "tags": self.tags,
+            "created": self.created_at,
+            "version": self.version,
+            "visible": self.visibility,
+            "category": {
+                "slug": self.category.slug,
+                "name": self.category.name,
+            },
+        })
+    }
+}
+
+impl Fields for Version {
+    fn fields(&self) -> Value {
+        json!({
+            "version": self.version,
+            "title": self.title,
+            "note": self.note,
+            "created": self.created_at,
+        })
+    }
+}
+
+impl Fields for CategoryItem {
+    fn fields(&self) -> Value {
+        json!({
+            "slug": self.slug,
+            "name": self.name,
+            "articles": self.article_count,
+        })
+    }
+}
+
+impl Template {
+    /// Parse `format`, or the template saved under that name in `templates`.
+    pub fn resolve(format: &str, templates: &HashMap<String, String>) -> Result<Self> {
+        Template::parse(templates.get(format).map_or(format, String::as_str))
+    }
+
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(invalid("unmatched `}`, write `}}` for a literal brace")),
+                '{' => {
+                    let mut expression = String::new();
+                    let mut quote = None;
+                    loop {
+                        let Some(c) = chars.next() else {
+                            return Err(invalid("unclosed `{`"));
+                        };
+                        match (quote, c) {
+                            (None, '}') => break,
+                            (None, '"' | '\'') => quote = Some(c),
+                            (Some(q), c) if q == c => quote = None,
+                            _ => {}
+                        }
+                        expression.push(c);
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(field(&expression)?);
+                }
+                _ => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    pub fn render<T: Fields>(&self, item: &T) -> Result<String> {
+        let fields = item.fields();
+        let mut line = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field { path, filters } => {
+                    let value = path
+                        .iter()
+                        .try_fold(&fields, |value, key| value.get(key))
+                        .cloned();
+
+                    let has_default = filters.iter().any(|f| matches!(f, Filter::Default(_)));
+                    let mut value = match value {
+                        Some(value) => value,
+                        None if has_default => Value::Null,
+                        None => {
+                            return Err(invalid(&format!("unknown field `{}`", path.join("."))))
+                        }
+                    };
+
+                    for filter in filters {
+                        value = filter.apply(value)?;
+                    }
+                    line.push_str(&to_text(&value));
+                }
+            }
+        }
+
+        Ok(line)
+    }
+
+    /// Render every item, one per line.
+    pub fn render_all<T: Fields>(&self, items: &[T]) -> Result<String> {
+        Ok(items
+            .iter()
+            .map(|item| self.render(item))
+            .collect::<Result<Vec<_>>>()?
+            .join("\n"))
+    }
+}
+
+impl Filter {
+    fn parse(expression: &str) -> Result<Self> {
+        let (name, args) = match expression.split_once('(') {
+            Some((name, rest)) => {
+                let args = rest
+                    .strip_suffix(')')
+                    .ok_or_else(|| invalid(&format!("missing `)` in `{expression}`")))?;
+                (name.trim(), arguments(args)?)
+            }
+            None => (expression.trim(), Vec::new()),
+        };
+
+        let mut args = args.into_iter();
+        let filter = match name {
+            "date" => Filter::Date(args.next().unwrap_or(DEFAULT_DATE_FORMAT.to_string())),
+            "join" => Filter::Join(args.next().unwrap_or(",".to_string())),
+            "upper" => Filter::Upper,
+            "lower" => Filter::Lower,
+            "default" => Filter::Default(args.next().unwrap_or_default()),
+            _ => {
+                return Err(invalid(&format!(
+                    "unknown filter `{name}`, available filters: date, join, upper, lower, default"
+                )))
+            }
+        };
+
+        if args.next().is_some() {
+            return Err(invalid(&format!("too many arguments for `{name}`")));
+        }
+        Ok(filter)
+    }
+
+    fn apply(&self, value: Value) -> Result<Value> {
+        let value = match self {
+            Filter::Date(format) => {
+                let Some(timestamp) = value.as_i64() else {
+                    return Err(invalid("`date` expects a timestamp"));
+                };
+                let date = DateTime::from_timestamp_millis(timestamp)
+                    .ok_or_else(|| invalid(&format!("timestamp out of range: {timestamp}")))?;
+                // 无效的格式在 to_string 中会 panic, 改为返回错误
+                let mut text = String::new();
+                write!(text, "{}", date.format(format))
+                    .map_err(|_| invalid(&format!("invalid date format `{format}`")))?;
+                Value::String(text)
+            }
+            Filter::Join(separator) => match value {
+                Value::Array(items) => Value::String(
+                    items
+                        .iter()
+                        .map(to_text)
+                        .collect::<Vec<_>>()
+                        .join(separator),
+                ),
+                value => value,
+            },
+            Filter::Upper => Value::String(to_text(&value).to_uppercase()),
+            Filter::Lower => Value::String(to_text(&value).to_lowercase()),
+            Filter::Default(default) => {
+                let empty = match &value {
+                    Value::Null => true,
+                    Value::String(s) => s.is_empty(),
+                    Value::Array(items) => items.is_empty(),
+                    _ => false,
+                };
+                if empty {
+                    Value::String(default.clone())
+                } else {
+                    value
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+fn field(expression: &str) -> Result<Part> {
+    let mut pieces = split_outside_quotes(expression, '|').into_iter();
+    let path = pieces.next().unwrap_or_default();
+    let path = path.trim();
+
+    if path.is_empty() {
+        return Err(invalid("empty field in `{}`"));
+    }
+    let path = path.split('.').map(str::to_string).collect::<Vec<_>>();
+    if path.iter().any(String::is_empty) {
+        return Err(invalid(&format!("invalid field `{}`", path.join("."))));
+    }
+
+    let filters = pieces
+        .map(|filter| Filter::parse(&filter))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Part::Field { path, filters })
+}
+
+/// Comma separated, quoted arguments of a filter, e.g. `"%Y", ","`.
+fn arguments(args: &str) -> Result<Vec<String>> {
+    if args.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    split_outside_quotes(args, ',')
+        .iter()
+        .map(|arg| {
+            let arg = arg.trim();
+            let mut chars = arg.chars();
+            match (chars.next(), chars.next_back()) {
+                (Some(open @ ('"' | '\'')), Some(close)) if open == close && arg.len() >= 2 => {
+                    Ok(chars.as_str().to_string())
+                }
+                _ => Err(invalid(&format!(
+                    "filter arguments must be quoted, got `{arg}`"
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn split_outside_quotes(text: &str, separator: char) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    let mut quote = None;
+
+    for c in text.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, c) if c == separator => {
+                pieces.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(piece) = pieces.last_mut() {
+            piece.push(c);
+        }
+    }
+    pieces
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(to_text).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidTemplate(reason.to_string())
+}
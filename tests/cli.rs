@@ -0,0 +1,988 @@
+mod support;
+
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use blc::api::{BlcClient, BlogBackend, Relogin, Session};
+use support::mock_server::MockServer;
+use support::{stderr, stdout, Blc};
+
+#[test]
+fn login_saves_the_token() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc.login("secret");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(blc.home().join(".blc").join("blc.b").exists());
+
+    let output = blc.run(&["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("token accepted"));
+}
+
+#[test]
+fn status_when_logged_out() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc.run(&["status"]);
+    assert_eq!(output.status.code(), Some(3));
+    let out = stdout(&output);
+    assert!(out.contains("Profile:     default"), "{out}");
+    assert!(
+        out.contains(&format!("Endpoint:    {}", server.url())),
+        "{out}"
+    );
+    assert!(out.contains("blc.b"), "{out}");
+    assert!(out.contains("Logged in:   no"), "{out}");
+
+    let output = blc
+        .command()
+        .env_remove("BLC_API")
+        .args(["whoami", "--output", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["ok"], false);
+    assert_eq!(document["error"]["kind"], "auth");
+    assert_eq!(document["data"]["profile"], "default");
+    assert_eq!(document["data"]["endpoint"], serde_json::Value::Null);
+    assert_eq!(document["data"]["logged_in"], false);
+}
+
+#[test]
+fn wrong_password() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc.login("wrong");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("Wrong password"));
+    assert!(!blc.home().join(".blc").join("blc.b").exists());
+}
+
+#[test]
+fn garbled_token() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    server.garble_token_next(1);
+    let output = blc.login("secret");
+    assert_eq!(output.status.code(), Some(8));
+    assert!(stderr(&output).contains("lacks necessary data"));
+    assert!(!blc.home().join(".blc").join("blc.b").exists());
+}
+
+#[test]
+fn publish_an_article() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    let article = blc.home().join("hello.md");
+    fs::write(&article, "# Hello, world\n\nFirst post.\n").unwrap();
+
+    for args in [
+        &["category", "create", "notes", "--name", "Notes"][..],
+        &["init", "hello", "--category", "notes"],
+        &["upload", "hello", article.to_str().unwrap()],
+        &["set", "hello", "--public"],
+        &["set", "hello", "--tags", "rust, cli"],
+    ] {
+        let output = blc.run(args);
+        assert!(output.status.success(), "{args:?}: {}", stderr(&output));
+    }
+
+    let output = blc.run(&[
+        "list",
+        "--tags",
+        "rust",
+        "--columns",
+        "visible,uri,category,tags",
+    ]);
+    assert!(
+        stdout(&output)
+            .starts_with("VIS  URI    CATEGORY  TAGS\n*    hello  notes     rust, cli\n"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(stdout(&output).contains("Total 1 items"));
+
+    let output = blc.run(&["versions", "hello"]);
+    assert!(
+        stdout(&output).contains("  Hello, world  "),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn missing_article() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    let output = blc.run(&["versions", "missing"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).contains("does not exist"));
+}
+
+#[test]
+fn server_error() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    server.fail_next(1);
+    let output = blc.run(&["list"]);
+    assert_eq!(output.status.code(), Some(8));
+
+    let output = blc.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn malformed_response() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    server.malform_next(1);
+    let output = blc.run(&["category", "list"]);
+    assert_eq!(output.status.code(), Some(8));
+    assert!(stderr(&output).contains("decoding"), "{}", stderr(&output));
+}
+
+#[test]
+fn request_timeout() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    server.set_latency(Duration::from_secs(3));
+    let output = blc
+        .command()
+        .env("BLC_TIMEOUT", "1")
+        .env("BLC_RETRIES", "0")
+        .arg("list")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[test]
+fn retry_stalled_body() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    // 响应头已收到, 读取响应体时超时
+    server.stall_body_next(1);
+    let output = blc.run(&["list", "--read-timeout", "1", "--retry-delay-ms", "0"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lists = server
+        .requests()
+        .iter()
+        .filter(|r| r.starts_with("GET /author/articles"))
+        .count();
+    assert_eq!(lists, 2);
+
+    server.stall_body_next(1);
+    let output = blc.run(&["list", "--read-timeout", "1", "--retries", "0"]);
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[test]
+fn html_error_pages() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+    blc.run(&["category", "create", "notes", "--name", "Notes"]);
+    blc.run(&["init", "hello", "--category", "notes"]);
+
+    let article = blc.home().join("hello.md");
+    fs::write(&article, "# Hello\n").unwrap();
+    server.html_next(413);
+    let output = blc.run(&["upload", "hello", article.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).contains("HTTP 413"), "{}", stderr(&output));
+
+    server.html_next(502);
+    let output = blc.run(&["list", "--retries", "0"]);
+    assert_eq!(output.status.code(), Some(6));
+    assert!(
+        stderr(&output).contains("502 Bad Gateway"),
+        "{}",
+        stderr(&output)
+    );
+
+    server.html_next(200);
+    let output = blc.run(&["list"]);
+    assert_eq!(output.status.code(), Some(8));
+    assert!(
+        stderr(&output).contains("got text/html"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = Blc::new(&format!("{}/api/v1", server.url())).login("secret");
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).contains("/api/v1/authentication (HTTP 404)"));
+}
+
+#[test]
+fn verbose_logging() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc.run_with_input(
+        &["login", "--password-stdin", "--verbose", "--verbose"],
+        "secret\n",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let log = stderr(&output);
+    assert!(
+        log.contains(&format!("POST {}/authentication -> 200 OK", server.url())),
+        "{log}"
+    );
+    assert!(log.contains(r#""password":"***""#), "{log}");
+    assert!(log.contains("x-auth-token: ***"), "{log}");
+    assert!(
+        !log.contains("secret") && !log.contains("memory-token"),
+        "{log}"
+    );
+
+    for args in [&["list", "--verbose", "--verbose"][..], &["-vv", "list"]] {
+        let output = blc.run(args);
+        assert!(output.status.success(), "{args:?}: {}", stderr(&output));
+        let log = stderr(&output);
+        assert!(log.contains("authorization: Bearer ***"), "{log}");
+        assert!(!log.contains("memory-token"), "{log}");
+    }
+
+    // 单个 --verbose 只记录请求行, 以及无法解析的响应内容
+    server.malform_next(1);
+    let output = blc.run(&["category", "list", "--verbose"]);
+    assert_eq!(output.status.code(), Some(8));
+    let log = stderr(&output);
+    assert!(log.contains(r#"body: {"code": "OK", "data": {"#), "{log}");
+    assert!(!log.contains("authorization"), "{log}");
+
+    let output = blc.run(&["category", "list"]);
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn through_proxy() {
+    let proxy = MockServer::start("secret");
+    let blc = Blc::new("http://blog.invalid");
+
+    let output = blc.run_with_input(
+        &["login", "--password-stdin", "--proxy", &proxy.url()],
+        "secret\n",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = blc
+        .command()
+        .env("BLC_PROXY", proxy.url())
+        .args(["list", "--insecure"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("verification is disabled"));
+
+    let requests = proxy.requests();
+    assert!(requests[0].starts_with("POST http://blog.invalid/authentication"));
+    assert!(requests[1].starts_with("GET http://blog.invalid/author/articles"));
+}
+
+#[test]
+fn invalid_tls_files() {
+    let blc = Blc::new("https://blog.example.com/api");
+    let list = |args: &[&str]| {
+        blc.command()
+            .env("BLC_TOKEN", "token")
+            .arg("list")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let missing = blc.home().join("missing.pem");
+    let output = list(&["--ca-cert", missing.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("missing.pem"),
+        "{}",
+        stderr(&output)
+    );
+
+    let garbage = blc.home().join("ca.pem");
+    fs::write(&garbage, "not a certificate").unwrap();
+    let output = list(&["--ca-cert", garbage.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("no PEM encoded certificate"));
+
+    let output = list(&[
+        "--client-cert",
+        garbage.to_str().unwrap(),
+        "--client-key",
+        garbage.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+}
+
+#[test]
+fn retry_unavailable() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    server.unavailable_next(2);
+    let output = blc.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lists = server
+        .requests()
+        .iter()
+        .filter(|r| r.starts_with("GET /author/articles"))
+        .count();
+    assert_eq!(lists, 3);
+
+    server.unavailable_next(3);
+    let output = blc.run(&["list", "--retries", "1"]);
+    assert_eq!(output.status.code(), Some(6));
+    server.unavailable_next(0);
+
+    // POST 默认不重试
+    blc.run(&["category", "create", "notes", "--name", "Notes"]);
+    server.unavailable_next(1);
+    let output = blc.run(&["init", "hello", "--category", "notes"]);
+    assert!(!output.status.success());
+
+    server.unavailable_next(1);
+    let output = blc.run(&["init", "hello", "--category", "notes", "--retry-post"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn profile_list_endpoints() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc.run_with_input(
+        &["login", "--password-stdin", "--profile", "work"],
+        "secret\n",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = blc
+        .command()
+        .env_remove("BLC_API")
+        .args(["profile", "list", "--output", "json"])
+        .output()
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let work = document["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|profile| profile["name"] == "work")
+        .unwrap()
+        .clone();
+    assert_eq!(work["endpoint"], server.url());
+    assert_eq!(work["logged_in"], true);
+}
+
+#[test]
+fn reassign_over_http() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    for args in [
+        &["category", "create", "old", "--name", "Old"][..],
+        &["category", "create", "new", "--name", "New"],
+        &["init", "a", "--category", "old"],
+        &["init", "b", "--category", "old"],
+        &["category", "delete", "old", "--reassign", "new"],
+    ] {
+        let output = blc.run(args);
+        assert!(output.status.success(), "{args:?}: {}", stderr(&output));
+    }
+    assert!(server
+        .requests()
+        .contains(&"PATCH /articles/a/category".to_string()));
+
+    let output = blc.run(&["category", "show", "new", "--output", "json"]);
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["data"]["articles"]["count"], 2);
+
+    let output = blc.run(&["category", "delete", "new", "--reassign", "new"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("being deleted"));
+
+    // 目标分类不存在时不移动任何文章
+    let moves = |server: &MockServer| {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.ends_with("/category"))
+            .count()
+    };
+    let before = moves(&server);
+    let output = blc.run(&["category", "delete", "new", "--reassign", "missing"]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert_eq!(moves(&server), before);
+}
+
+#[test]
+fn json_output() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+    blc.run(&["category", "create", "notes", "--name", "Notes"]);
+
+    let output = blc.run(&["category", "list", "--output", "json"]);
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["data"]["items"][0]["articleCount"], 0);
+
+    let output = blc
+        .command()
+        .env("BLC_OUTPUT", "json")
+        .args(["category", "show", "missing"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["error"]["code"], "RESOURCE_NOT_EXIST");
+    assert_eq!(document["error"]["kind"], "not_found");
+    assert_eq!(document["error"]["exit_code"], 4);
+}
+
+#[test]
+fn search_cache() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+    blc.run(&["category", "create", "notes", "--name", "Notes"]);
+    blc.run(&["init", "hello", "--category", "notes"]);
+
+    let output = blc.run(&["search", "hello"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let dir = blc.home().join(".blc").join("cache").join("default");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode =
+            |path: &std::path::Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&dir.join("hello.json")), 0o600);
+    }
+
+    // ~ 展开为主目录, 而不是当前目录下的 ~
+    let output = blc
+        .command()
+        .env("BLC_CACHE_DIR", "~/cache")
+        .args(["search", "hello"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(blc
+        .home()
+        .join("cache")
+        .join("default")
+        .join("hello.json")
+        .exists());
+    assert!(!blc.home().join("~").exists());
+
+    // $BLC_TOKEN 不使用默认缓存
+    let ephemeral = Blc::new(&server.url());
+    let output = ephemeral
+        .command()
+        .env("BLC_TOKEN", server.blog().token().unwrap())
+        .args(["search", "hello"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!ephemeral.home().join(".blc").exists());
+}
+
+#[test]
+fn without_home() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc
+        .command()
+        .env_remove("HOME")
+        .arg("list")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(7));
+    assert!(
+        stderr(&output).contains("home directory"),
+        "{}",
+        stderr(&output)
+    );
+
+    // $BLC_TOKEN 和 --api 不需要主目录
+    let output = blc
+        .command()
+        .env_remove("HOME")
+        .env("BLC_TOKEN", server.blog().token().unwrap())
+        .arg("list")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn configuration_errors() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+
+    let output = blc.run(&["config", "get", "page_limit", "--output", "json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["error"]["kind"], "usage");
+    assert_eq!(document["error"]["exit_code"], 2);
+    assert_eq!(document["error"]["message"], "`page_limit` is not set.");
+
+    fs::write(blc.home().join("blc.toml"), "page_limit = \"many\"\n").unwrap();
+    let output = blc.run(&["list"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("configuration error"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = blc.run(&["list", "--output", "json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["ok"], false);
+    assert_eq!(document["error"]["kind"], "usage");
+
+    // config 命令仍可用来修正配置, 并指出有误的文件和键
+    let output = blc.run(&["config", "get", "page_limit"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "many\n");
+    assert!(
+        stderr(&output).contains("`page_limit` in blc.toml"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = blc.run(&["config", "set", "page_limit", "5", "--project"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = blc.run(&["config", "get", "page_limit"]);
+    assert_eq!(stdout(&output), "5\n");
+    assert!(stderr(&output).is_empty(), "{}", stderr(&output));
+}
+
+#[test]
+fn templated_list() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+    blc.run(&["category", "create", "notes", "--name", "Notes"]);
+    blc.run(&["init", "hello", "--category", "notes"]);
+    blc.run(&["set", "hello", "--tags", "rust, cli"]);
+
+    let output = blc.run(&[
+        "list",
+        "--format",
+        r#"{uri}\t{category.slug}\t{tags|join(",")}"#,
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "hello\tnotes\trust,cli\n");
+
+    blc.run(&["config", "set", "templates.counts", "{slug}: {articles}"]);
+    let output = blc.run(&["category", "list", "--format", "counts"]);
+    assert_eq!(stdout(&output), "notes: 1\n");
+}
+
+#[test]
+fn encoded_paths() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    let article = blc.home().join("post.md");
+    fs::write(&article, "# 你好\n").unwrap();
+
+    let uri = "hello world&#/你好";
+    for args in [
+        &["category", "create", "c++ & co", "--name", "C++"][..],
+        &["init", uri, "--category", "c++ & co"],
+        &["upload", uri, article.to_str().unwrap()],
+        &["set", uri, "--category", "c++ & co"],
+        &["set", uri, "--tags", "a&b, #c"],
+    ] {
+        let output = blc.run(args);
+        assert!(output.status.success(), "{args:?}: {}", stderr(&output));
+    }
+
+    let version = server.blog().get_article_version_list(uri);
+    let version = futures::executor::block_on(version).unwrap();
+    let version = &version.data().as_ref().unwrap().items[0].version;
+    let output = blc.run(&["set", uri, "--version", version]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = blc.run(&["list", "--tags", "a&b", "--format", "{uri}"]);
+    assert_eq!(stdout(&output), format!("{uri}\n"));
+
+    let requests = server.requests();
+    let encoded = "hello%20world&%23%2F%E4%BD%A0%E5%A5%BD";
+    for request in [
+        format!("PATCH /articles/{encoded}/category"),
+        format!("PATCH /articles/{encoded}/tags"),
+        format!("PUT /articles/{encoded}/version"),
+        "GET /author/articles?page=1&tags=a%26b".to_string(),
+    ] {
+        assert!(
+            requests.contains(&request),
+            "{request} not in {requests:#?}"
+        );
+    }
+}
+
+/// Answers the re-login prompt with a fixed password and records what happened.
+#[derive(Clone, Default)]
+struct FakeRelogin {
+    password: Option<String>,
+    prompts: Arc<AtomicUsize>,
+    renewed: Arc<Mutex<Vec<String>>>,
+}
+
+impl Relogin for FakeRelogin {
+    fn password(&self) -> Option<String> {
+        self.prompts.fetch_add(1, Ordering::SeqCst);
+        self.password.clone()
+    }
+
+    fn renewed(&self, session: &Session) -> blc::Result<()> {
+        self.renewed.lock().unwrap().push(session.token.clone());
+        Ok(())
+    }
+}
+
+fn count(server: &MockServer, prefix: &str) -> usize {
+    server
+        .requests()
+        .iter()
+        .filter(|request| request.starts_with(prefix))
+        .count()
+}
+
+#[tokio::test]
+async fn relogin_on_rejected_token() {
+    let server = MockServer::start("secret");
+    let relogin = FakeRelogin {
+        password: Some("secret".to_string()),
+        ..Default::default()
+    };
+    let client = BlcClient::builder()
+        .endpoint(server.url())
+        .token("expired-token")
+        .relogin(relogin.clone())
+        .build()
+        .unwrap();
+
+    let articles = client.get_article_list(1, None, None, None).await.unwrap();
+    assert!(articles.data().is_some());
+    assert_eq!(relogin.prompts.load(Ordering::SeqCst), 1);
+    assert_eq!(count(&server, "POST /authentication"), 1);
+    assert_eq!(count(&server, "GET /author/articles"), 2);
+
+    let renewed = relogin.renewed.lock().unwrap().clone();
+    assert_eq!(renewed.len(), 1);
+    assert_eq!(client.token(), Some(renewed[0].clone()));
+
+    // 每个客户端最多重新登录一次
+    server.blog().revoke_token(true).await.unwrap();
+    let err = client
+        .get_article_list(1, None, None, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.exit_code(), 3, "{err}");
+    assert_eq!(relogin.prompts.load(Ordering::SeqCst), 1);
+    assert_eq!(count(&server, "POST /authentication"), 1);
+    assert_eq!(count(&server, "GET /author/articles"), 3);
+}
+
+#[tokio::test]
+async fn relogin_declined_or_failed() {
+    let server = MockServer::start("secret");
+
+    for password in [None, Some("wrong")] {
+        let before = count(&server, "GET /author/articles");
+        let relogin = FakeRelogin {
+            password: password.map(str::to_string),
+            ..Default::default()
+        };
+        let client = BlcClient::builder()
+            .endpoint(server.url())
+            .token("expired-token")
+            .relogin(relogin.clone())
+            .build()
+            .unwrap();
+
+        let err = client
+            .get_article_list(1, None, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 3, "{err}");
+        assert_eq!(relogin.prompts.load(Ordering::SeqCst), 1);
+        assert!(relogin.renewed.lock().unwrap().is_empty());
+        // 未能重新登录时不重发原请求
+        assert_eq!(count(&server, "GET /author/articles"), before + 1);
+    }
+    assert_eq!(count(&server, "POST /authentication"), 1);
+}
+
+#[test]
+fn env_token_never_relogs_in() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    let output = blc.login("secret");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let saved = fs::read(blc.home().join(".blc").join("blc.b")).unwrap();
+
+    let output = blc
+        .command()
+        .env("BLC_TOKEN", "revoked-token")
+        .arg("list")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(
+        !stderr(&output).contains("log in again"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(count(&server, "POST /authentication"), 1);
+    assert_eq!(
+        fs::read(blc.home().join(".blc").join("blc.b")).unwrap(),
+        saved
+    );
+}
+
+#[test]
+fn token_expiry_warning() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    let path = blc.home().join(".blc").join("blc.b");
+
+    let output = blc
+        .command()
+        .env("BLC_TOKEN_LIFETIME", "3600")
+        .env("BLC_PASSWORD", "secret")
+        .arg("login")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = blc.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        !stderr(&output).contains("login token"),
+        "{}",
+        stderr(&output)
+    );
+
+    // 登录于100秒前, 10秒后过期: 剩余不足五分之一
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut bytes = fs::read(&path).unwrap();
+    let end = bytes.len();
+    bytes[5..13].copy_from_slice(&(now - 100_000).to_be_bytes());
+    bytes[end - 8..].copy_from_slice(&(now + 10_000).to_be_bytes());
+    fs::write(&path, &bytes).unwrap();
+
+    let output = blc.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("The login token expires in"),
+        "{}",
+        stderr(&output)
+    );
+
+    bytes[end - 8..].copy_from_slice(&(now - 1_000).to_be_bytes());
+    fs::write(&path, &bytes).unwrap();
+    let output = blc.run(&["list"]);
+    assert!(
+        stderr(&output).contains("The login token has expired"),
+        "{}",
+        stderr(&output)
+    );
+
+    // $BLC_TOKEN 不读取保存的登录, 也就没有提醒
+    let token = server.blog().token().unwrap();
+    let output = blc
+        .command()
+        .env("BLC_TOKEN", token)
+        .arg("list")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        !stderr(&output).contains("login token"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn env_token_is_ephemeral() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    let token = server.blog().token().unwrap();
+    let with_token = |args: &[&str]| {
+        blc.command()
+            .env("BLC_TOKEN", &token)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    fs::write(blc.home().join("hello.md"), "# Hello\n").unwrap();
+    for args in [
+        &["category", "create", "notes", "--name", "Notes"][..],
+        &["init", "hello", "--category", "notes"],
+        &["upload", "hello", "hello.md"],
+        &["list"],
+        &["search", "hello"],
+        &["status"],
+    ] {
+        let output = with_token(args);
+        assert!(output.status.success(), "{args:?}: {}", stderr(&output));
+    }
+    assert!(!blc.home().join(".blc").exists());
+
+    // 保存的登录既不读取也不改写, 即使已经损坏
+    let output = blc.login("secret");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let path = blc.home().join(".blc").join("blc.b");
+    fs::write(&path, b"AUTH\x02").unwrap();
+
+    let output = with_token(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read(&path).unwrap(), b"AUTH\x02");
+    assert_eq!(
+        fs::read_dir(blc.home().join(".blc")).unwrap().count(),
+        1,
+        "only the saved login"
+    );
+}
+
+#[test]
+fn logout_revokes_the_token() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    let other = Blc::new(&server.url());
+    for blc in [&blc, &other] {
+        let output = blc.login("secret");
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+
+    let output = blc.run(&["logout", "--output", "json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["data"]["all_sessions"], false);
+    assert_eq!(
+        document["data"]["profiles"],
+        serde_json::json!([{ "profile": "default", "revoked": true, "error": null }])
+    );
+    assert!(!blc.home().join(".blc").join("blc.b").exists());
+
+    // 其他会话不受影响, --all-sessions 则全部失效
+    let output = other.run(&["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    blc.login("secret");
+    let output = blc.run(&["logout", "--all-sessions", "--output", "json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["data"]["all_sessions"], true);
+    assert_eq!(document["data"]["profiles"][0]["revoked"], true);
+    assert!(server
+        .requests()
+        .contains(&"DELETE /authentication/sessions".to_string()));
+    let output = other.run(&["status"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+
+    let output = other.run(&["logout", "--local-only", "--output", "json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["data"]["profiles"][0]["revoked"], false);
+    assert_eq!(
+        document["data"]["profiles"][0]["error"],
+        serde_json::Value::Null
+    );
+}
+
+#[test]
+fn logout_when_revoking_fails() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+
+    server.fail_next(1);
+    let output = blc.run(&["logout", "--retries", "0", "--output", "json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["ok"], true);
+    let report = &document["data"]["profiles"][0];
+    assert_eq!(report["profile"], "default");
+    assert_eq!(report["revoked"], false);
+    assert!(report["error"].is_string(), "{report}");
+    // 本地的登录照常清除
+    assert!(!blc.home().join(".blc").join("blc.b").exists());
+
+    blc.login("secret");
+    server.fail_next(1);
+    let output = blc.run(&["logout", "--retries", "0"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("Could not revoke the token of profile default"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(!stdout(&output).contains("revoked on the server"));
+}
+
+#[test]
+fn saved_endpoint_over_api_key() {
+    let server = MockServer::start("secret");
+    let blc = Blc::new(&server.url());
+    blc.login("secret");
+    let config = |args: &[&str]| {
+        blc.command()
+            .env_remove("BLC_API")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = config(&["config", "set", "api", "http://127.0.0.1:9"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains(&format!(
+            "Profile default is logged in to {}, which takes precedence over `api`",
+            server.url()
+        )),
+        "{}",
+        stderr(&output)
+    );
+
+    // 登录时保存的地址仍然生效
+    let output = config(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = config(&["config", "set", "api", &format!("{}/", server.url())]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).is_empty(), "{}", stderr(&output));
+}
@@ -0,0 +1,371 @@
+use std::fs;
+
+use blc::api::fake::MemoryBackend;
+use blc::api::response::ResponseCode;
+use blc::api::BlogBackend;
+use blc::output::{OutputFormat, Printer};
+use blc::settings::Settings;
+use blc::{commands, config, Cli, Error};
+use clap::Parser;
+
+/// Run `blc <args>` against the backend, returning the finished message and the output.
+async fn run(backend: &MemoryBackend, args: &[&str]) -> blc::Result<(Option<String>, String)> {
+    run_as(backend, OutputFormat::Text, args).await
+}
+
+async fn run_as(
+    backend: &MemoryBackend,
+    format: OutputFormat,
+    args: &[&str],
+) -> blc::Result<(Option<String>, String)> {
+    colored::control::set_override(false);
+
+    let cli = Cli::try_parse_from(std::iter::once("blc").chain(args.iter().copied())).unwrap();
+    let mut out = Vec::new();
+    let mut printer = Printer::new(format, &mut out);
+    let info = commands::run(
+        backend,
+        &cli.action,
+        &Settings::default(),
+        config::DEFAULT_PROFILE,
+        &mut printer,
+    )
+    .await?;
+    printer.finish(info.as_deref())?;
+    Ok((info, String::from_utf8(out).unwrap()))
+}
+
+#[tokio::test]
+async fn publish_an_article() {
+    let blog = MemoryBackend::new("secret");
+    let path = std::env::temp_dir().join(format!("blc-commands-{}.md", std::process::id()));
+    fs::write(&path, "# Hello\n").unwrap();
+
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    run(&blog, &["init", "hello", "--category", "notes"])
+        .await
+        .unwrap();
+    run(&blog, &["upload", "hello", path.to_str().unwrap()])
+        .await
+        .unwrap();
+    run(&blog, &["set", "hello", "--public"]).await.unwrap();
+    run(&blog, &["set", "hello", "--tags", "rust, cli"])
+        .await
+        .unwrap();
+
+    let (info, out) = run(
+        &blog,
+        &["list", "--columns", "visible,uri,tags", "--no-header"],
+    )
+    .await
+    .unwrap();
+    assert_eq!(info.as_deref(), Some("Query done. Total 1 items."));
+    assert!(
+        out.starts_with("*  hello  rust, cli\n\ncurrent page: 1\n"),
+        "{out}"
+    );
+
+    let (_, out) = run(&blog, &["versions", "hello", "--columns", "title"])
+        .await
+        .unwrap();
+    assert!(
+        out.starts_with("Article hello version list:\nTITLE\nHello\n"),
+        "{out}"
+    );
+
+    let resp = blog
+        .get_article_list(1, None, None, Some("cli"))
+        .await
+        .unwrap();
+    assert_eq!(resp.data().as_ref().unwrap().count, 1);
+}
+
+#[tokio::test]
+async fn init_without_category() {
+    let blog = MemoryBackend::new("secret");
+
+    let result = run(&blog, &["init", "hello"]).await;
+    assert!(matches!(result, Err(Error::MissingCategory)));
+}
+
+#[tokio::test]
+async fn missing_article() {
+    let blog = MemoryBackend::new("secret");
+
+    let result = run(&blog, &["versions", "missing"]).await;
+    assert!(
+        matches!(result, Err(Error::ResponseError { code, .. }) if code == ResponseCode::ResourceNotExist)
+    );
+}
+
+#[tokio::test]
+async fn delete_category_with_articles() {
+    let blog = MemoryBackend::new("secret");
+    run(&blog, &["category", "create", "old", "-n", "Old"])
+        .await
+        .unwrap();
+    run(&blog, &["category", "create", "new", "-n", "New"])
+        .await
+        .unwrap();
+    run(&blog, &["init", "a", "-c", "old"]).await.unwrap();
+    run(&blog, &["init", "b", "-c", "old"]).await.unwrap();
+
+    let result = run(&blog, &["category", "delete", "old"]).await;
+    assert!(matches!(
+        result,
+        Err(Error::CategoryNotEmpty { count: 2, .. })
+    ));
+
+    let result = run(&blog, &["category", "delete", "old", "--reassign", "old"]).await;
+    assert!(matches!(result, Err(Error::ReassignToSelf(_))));
+
+    let result = run(
+        &blog,
+        &["category", "delete", "old", "--reassign", "missing"],
+    )
+    .await;
+    assert_eq!(result.unwrap_err().exit_code(), 4);
+
+    // 以上失败都没有移动文章
+    let (_, out) = run(&blog, &["category", "show", "old"]).await.unwrap();
+    assert!(out.contains("1. ") && out.contains("2. "), "{out}");
+
+    let (_, out) = run(&blog, &["category", "delete", "old", "--reassign", "new"])
+        .await
+        .unwrap();
+    assert_eq!(
+        out,
+        "Moved 2 article(s) to new\n✓ Finished: Category deleted, slug: old\n"
+    );
+
+    let (_, out) = run(&blog, &["category", "list"]).await.unwrap();
+    assert!(
+        out.starts_with("1. New(new) - 2 article(s)\n\n✓ Finished"),
+        "{out}"
+    );
+}
+
+#[tokio::test]
+async fn structured_output() {
+    let blog = MemoryBackend::new("secret");
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    run(&blog, &["init", "a", "-c", "notes"]).await.unwrap();
+    run(&blog, &["init", "b", "-c", "notes"]).await.unwrap();
+
+    let (_, out) = run_as(&blog, OutputFormat::Json, &["list"]).await.unwrap();
+    let document: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(document["ok"], true);
+    assert_eq!(document["data"]["count"], 2);
+    assert_eq!(document["data"]["items"][0]["uri"], "b");
+    assert_eq!(document["data"]["items"][0]["category"]["slug"], "notes");
+
+    let (_, out) = run_as(&blog, OutputFormat::Ndjson, &["list"])
+        .await
+        .unwrap();
+    let uris = out
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["uri"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(uris, ["b", "a"]);
+
+    let (_, out) = run_as(&blog, OutputFormat::Yaml, &["init", "c", "-c", "notes"])
+        .await
+        .unwrap();
+    assert_eq!(
+        out,
+        "ok: true\nmessage: 'Article Initialization, uri: c'\ndata: null\n"
+    );
+}
+
+#[tokio::test]
+async fn structured_error() {
+    let blog = MemoryBackend::new("secret");
+    let e = run(&blog, &["versions", "missing"]).await.unwrap_err();
+
+    let mut out = Vec::new();
+    Printer::new(OutputFormat::Json, &mut out)
+        .error(&e)
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(document["ok"], false);
+    assert_eq!(document["error"]["code"], "RESOURCE_NOT_EXIST");
+}
+
+#[tokio::test]
+async fn list_all_pages() {
+    let blog = MemoryBackend::new("secret");
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    for uri in ["one", "two", "three"] {
+        run(&blog, &["init", uri, "--category", "notes"])
+            .await
+            .unwrap();
+    }
+
+    let (info, out) = run(
+        &blog,
+        &["list", "--all", "--limit", "2", "--format", "{uri}"],
+    )
+    .await
+    .unwrap();
+    assert_eq!(info, None);
+    assert_eq!(out, "three\ntwo\none\n");
+
+    let (_, out) = run_as(
+        &blog,
+        OutputFormat::Ndjson,
+        &["list", "--max", "2", "--limit", "1"],
+    )
+    .await
+    .unwrap();
+    assert_eq!(out.lines().count(), 2);
+
+    let (info, out) = run(
+        &blog,
+        &[
+            "list",
+            "--all",
+            "--limit",
+            "2",
+            "--sort",
+            "uri",
+            "--columns",
+            "uri",
+            "--no-header",
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(info.as_deref(), Some("Query done. Total 3 items."));
+    assert!(out.starts_with("one\nthree\ntwo\n"), "{out}");
+}
+
+#[tokio::test]
+async fn list_all_table_by_page() {
+    let blog = MemoryBackend::new("secret");
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    for uri in ["one", "two", "three"] {
+        run(&blog, &["init", uri, "--category", "notes"])
+            .await
+            .unwrap();
+    }
+
+    let (info, out) = run(
+        &blog,
+        &["list", "--all", "--limit", "2", "--columns", "uri,category"],
+    )
+    .await
+    .unwrap();
+    assert_eq!(info.as_deref(), Some("Query done. Total 3 items."));
+    // 表头只输出一次, 各页的列宽保持一致
+    assert!(
+        out.starts_with("URI    CATEGORY\nthree  notes\ntwo    notes\none    notes\n"),
+        "{out:?}"
+    );
+}
+
+#[tokio::test]
+async fn list_where() {
+    let blog = MemoryBackend::new("secret");
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    run(&blog, &["category", "create", "posts", "-n", "Posts"])
+        .await
+        .unwrap();
+    for (uri, category) in [("one", "notes"), ("two", "posts"), ("three", "notes")] {
+        run(&blog, &["init", uri, "--category", category])
+            .await
+            .unwrap();
+    }
+    run(&blog, &["set", "three", "--tags", "rust"])
+        .await
+        .unwrap();
+
+    let (_, out) = run(
+        &blog,
+        &[
+            "list",
+            "--limit",
+            "1",
+            "--where",
+            "category=notes",
+            "--format",
+            "{uri}",
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(out, "three\none\n");
+
+    let (info, _) = run(&blog, &["list", "--where", "not tag:rust and uri~o"])
+        .await
+        .unwrap();
+    assert_eq!(info.as_deref(), Some("Query done. Showing 2 of 3 items."));
+}
+
+#[tokio::test]
+async fn set_version_short_flag() {
+    let blog = MemoryBackend::new("secret");
+    let path = std::env::temp_dir().join(format!("blc-set-version-{}.md", std::process::id()));
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    run(&blog, &["init", "hello", "-c", "notes"]).await.unwrap();
+    for title in ["# First\n", "# Second\n"] {
+        fs::write(&path, title).unwrap();
+        run(&blog, &["upload", "hello", path.to_str().unwrap()])
+            .await
+            .unwrap();
+    }
+
+    let versions = blog.get_article_version_list("hello").await.unwrap();
+    let versions = versions.data().as_ref().unwrap();
+    let first = versions
+        .items
+        .iter()
+        .find(|v| v.title == "First")
+        .unwrap()
+        .version
+        .clone();
+
+    // -v 属于 set --version, 不是全局的 --verbose
+    run(&blog, &["set", "hello", "-v", &first]).await.unwrap();
+    let resp = blog.get_article_list(1, None, None, None).await.unwrap();
+    assert_eq!(resp.data().as_ref().unwrap().items[0].version, first);
+}
+
+#[tokio::test]
+async fn show_category_with_all_pages() {
+    let blog = MemoryBackend::new("secret");
+    run(&blog, &["category", "create", "notes", "-n", "Notes"])
+        .await
+        .unwrap();
+    for i in 0..25 {
+        run(&blog, &["init", &format!("note-{i}"), "-c", "notes"])
+            .await
+            .unwrap();
+    }
+
+    let (info, _) = run(&blog, &["category", "show", "notes"]).await.unwrap();
+    assert_eq!(info.as_deref(), Some("Query done. Total 25 items."));
+
+    let (_, out) = run_as(&blog, OutputFormat::Json, &["category", "show", "notes"])
+        .await
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        document["data"]["articles"]["items"]
+            .as_array()
+            .unwrap()
+            .len(),
+        25
+    );
+}
@@ -0,0 +1,240 @@
+mod support;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use support::mock_server::MockServer;
+use support::{stderr, stdout, Blc};
+
+/// The fields of a version 2 credential file, to craft other layouts from.
+struct Saved {
+    timestamp: [u8; 8],
+    token: Vec<u8>,
+    endpoint: Vec<u8>,
+    expires_at: [u8; 8],
+}
+
+impl Saved {
+    fn read(path: &Path) -> Saved {
+        let bytes = fs::read(path).unwrap();
+        assert_eq!(&bytes[..5], b"AUTH\x02");
+
+        let len = |at: usize| u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        let token_len = len(13);
+        let endpoint_at = 17 + token_len;
+        let endpoint_len = len(endpoint_at);
+        let expires_at = endpoint_at + 4 + endpoint_len;
+
+        Saved {
+            timestamp: bytes[5..13].try_into().unwrap(),
+            token: bytes[17..endpoint_at].to_vec(),
+            endpoint: bytes[endpoint_at + 4..expires_at].to_vec(),
+            expires_at: bytes[expires_at..].try_into().unwrap(),
+        }
+    }
+
+    /// The version 0 layout: only the login time and the token.
+    fn v0(&self) -> Vec<u8> {
+        let mut bytes = b"AUTH\x00".to_vec();
+        bytes.extend(self.timestamp);
+        bytes.extend((self.token.len() as u32).to_be_bytes());
+        bytes.extend(&self.token);
+        bytes
+    }
+
+    fn v2(&self, token: &[u8]) -> Vec<u8> {
+        let mut bytes = b"AUTH\x02".to_vec();
+        bytes.extend(self.timestamp);
+        bytes.extend((token.len() as u32).to_be_bytes());
+        bytes.extend(token);
+        bytes.extend((self.endpoint.len() as u32).to_be_bytes());
+        bytes.extend(&self.endpoint);
+        bytes.extend(self.expires_at);
+        bytes
+    }
+}
+
+fn logged_in(server: &MockServer) -> (Blc, PathBuf) {
+    let blc = Blc::new(&server.url());
+    let output = blc.login("secret");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let path = blc.home().join(".blc").join("blc.b");
+    (blc, path)
+}
+
+#[test]
+fn upgrade_version_0() {
+    let server = MockServer::start("secret");
+    let (blc, path) = logged_in(&server);
+    let saved = Saved::read(&path);
+    fs::write(&path, saved.v0()).unwrap();
+
+    let output = blc.run(&["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("token accepted"));
+    assert!(
+        stderr(&output).contains("upgraded to format version 2"),
+        "{}",
+        stderr(&output)
+    );
+
+    // 升级后的文件按当前格式保存, 旧格式没有地址与有效期
+    let upgraded = fs::read(&path).unwrap();
+    assert_eq!(&upgraded[..5], b"AUTH\x02");
+    let upgraded = Saved::read(&path);
+    assert_eq!(upgraded.token, saved.token);
+    assert!(upgraded.endpoint.is_empty());
+
+    let output = blc.run(&["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("upgraded"), "{}", stderr(&output));
+}
+
+#[test]
+fn corrupt_files() {
+    let server = MockServer::start("secret");
+    let (blc, path) = logged_in(&server);
+    let valid = fs::read(&path).unwrap();
+    let saved = Saved::read(&path);
+
+    let mut newer = valid.clone();
+    newer[4] = 3;
+    let mut trailing = valid.clone();
+    trailing.extend(b"junk");
+    let mut no_magic = valid.clone();
+    no_magic[..4].copy_from_slice(b"HTUA");
+
+    let cases = [
+        (valid[..valid.len() - 3].to_vec(), "the file is truncated"),
+        (valid[..7].to_vec(), "the file is truncated"),
+        (newer, "format version 3 is newer"),
+        (trailing, "unexpected data after the end of the file"),
+        (saved.v2(b""), "the token is empty"),
+        (no_magic, "not a blc credential file"),
+        (Vec::new(), "not a blc credential file"),
+    ];
+    for (bytes, reason) in cases {
+        fs::write(&path, &bytes).unwrap();
+
+        let output = blc.run(&["status"]);
+        assert_eq!(
+            output.status.code(),
+            Some(3),
+            "{reason}: {}",
+            stderr(&output)
+        );
+        let err = stderr(&output);
+        assert!(err.contains(reason), "{err}");
+        assert!(err.contains("Run `blc login`"), "{err}");
+        assert!(!err.contains("panicked"), "{err}");
+
+        let output = blc.run(&["list", "--output", "json"]);
+        assert_eq!(
+            output.status.code(),
+            Some(3),
+            "{reason}: {}",
+            stderr(&output)
+        );
+        let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(document["error"]["kind"], "auth");
+    }
+
+    // 重新登录覆盖损坏的文件
+    let output = blc.login("secret");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = blc.run(&["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[cfg(unix)]
+fn mode(path: &Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[cfg(unix)]
+#[test]
+fn private_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = MockServer::start("secret");
+    let (blc, path) = logged_in(&server);
+    assert_eq!(mode(path.parent().unwrap()), 0o700);
+    assert_eq!(mode(&path), 0o600);
+
+    // 过于宽松的权限会被收紧
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    let output = blc.run(&["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("is accessible by other users (644), restricting it to 600"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(mode(&path), 0o600);
+
+    let output = blc.run(&["status"]);
+    assert!(stderr(&output).is_empty(), "{}", stderr(&output));
+}
+
+#[cfg(unix)]
+#[test]
+fn encrypted_store() {
+    let server = MockServer::start("secret");
+    let (blc, path) = logged_in(&server);
+    let plain = fs::read(&path).unwrap();
+
+    let encrypted = |passphrase: &str, args: &[&str]| {
+        blc.command()
+            .env("BLC_CREDENTIAL_STORE", "encrypted")
+            .env("BLC_PASSPHRASE", passphrase)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    // 已有的明文文件在读取时迁移
+    let output = encrypted("hunter2", &["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("migrated to the encrypted store"),
+        "{}",
+        stderr(&output)
+    );
+    let bytes = fs::read(&path).unwrap();
+    assert!(bytes.starts_with(b"age-encryption.org/"));
+    assert!(!bytes.windows(plain.len() - 5).any(|w| w == &plain[5..]));
+    assert_eq!(mode(&path), 0o600);
+
+    let output = encrypted("hunter2", &["status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("token accepted"));
+    assert!(stderr(&output).is_empty(), "{}", stderr(&output));
+
+    // 新的登录同样加密保存
+    let output = blc
+        .command()
+        .env("BLC_CREDENTIAL_STORE", "encrypted")
+        .env("BLC_PASSPHRASE", "hunter2")
+        .env("BLC_PASSWORD", "secret")
+        .arg("login")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(fs::read(&path).unwrap().starts_with(b"age-encryption.org/"));
+    let output = encrypted("hunter2", &["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = encrypted("wrong", &["status"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    let err = stderr(&output);
+    assert!(err.contains("credential store error"), "{err}");
+    assert!(!err.contains("panicked"), "{err}");
+    assert!(fs::read(&path).unwrap().starts_with(b"age-encryption.org/"));
+
+    let output = encrypted("wrong", &["list", "--output", "json"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["error"]["kind"], "auth");
+}
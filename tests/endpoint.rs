@@ -0,0 +1,148 @@
+use blc::api::endpoint::{self, *};
+use blc::Error;
+use reqwest::Method;
+
+const BASE: &str = "https://blog.example.com/api/v1";
+
+/// `METHOD path?query` of an endpoint below [`BASE`].
+fn request<E: Endpoint>(endpoint: E) -> String {
+    let url = endpoint::url(BASE, &endpoint).unwrap();
+    let path = &url.as_str()[BASE.len()..];
+    format!("{} {path}", E::METHOD)
+}
+
+#[test]
+fn methods_and_paths() {
+    let uri = "hello";
+    let list = ArticleList {
+        page: 2,
+        limit: Some(5),
+        category: Some("rust"),
+        tags: Some("a,b"),
+    };
+
+    let cases = [
+        (
+            request(Authenticate { password: "" }),
+            "POST /authentication",
+        ),
+        (
+            request(RevokeToken {
+                all_sessions: false,
+            }),
+            "DELETE /authentication",
+        ),
+        (
+            request(RevokeToken { all_sessions: true }),
+            "DELETE /authentication/sessions",
+        ),
+        (
+            request(list),
+            "GET /author/articles?page=2&limit=5&category=rust&tags=a%2Cb",
+        ),
+        (
+            request(CreateArticle { uri, category: "" }),
+            "POST /articles",
+        ),
+        (request(DeleteArticle { uri }), "DELETE /articles/hello"),
+        (
+            request(UploadVersion { uri }),
+            "POST /articles/hello/versions",
+        ),
+        (request(VersionList { uri }), "GET /articles/hello/versions"),
+        (
+            request(DeleteVersion { uri, version: "v1" }),
+            "DELETE /articles/hello/versions/v1",
+        ),
+        (
+            request(SetVisibility {
+                uri,
+                visibility: true,
+            }),
+            "PATCH /articles/hello/visibility",
+        ),
+        (
+            request(SetCategory { uri, category: "" }),
+            "PATCH /articles/hello/category",
+        ),
+        (
+            request(SetTags {
+                uri,
+                tags: Vec::new(),
+            }),
+            "PATCH /articles/hello/tags",
+        ),
+        (
+            request(SetVersion { uri, version: "" }),
+            "PUT /articles/hello/version",
+        ),
+        (request(CategoryList), "GET /categories"),
+        (
+            request(GetCategory { slug: "rust" }),
+            "GET /categories/rust",
+        ),
+        (
+            request(CreateCategory { slug: "", name: "" }),
+            "POST /categories",
+        ),
+        (
+            request(RenameCategory {
+                slug: "rust",
+                name: "",
+            }),
+            "PATCH /categories/rust",
+        ),
+        (
+            request(DeleteCategory { slug: "rust" }),
+            "DELETE /categories/rust",
+        ),
+    ];
+
+    for (actual, expected) in cases {
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn percent_encoding() {
+    assert_eq!(
+        request(SetVersion {
+            uri: "a b?#/中",
+            version: "1"
+        }),
+        "PUT /articles/a%20b%3F%23%2F%E4%B8%AD/version"
+    );
+    assert_eq!(
+        request(ArticleList {
+            page: 1,
+            limit: None,
+            category: Some("c&d"),
+            tags: Some("#x y")
+        }),
+        "GET /author/articles?page=1&category=c%26d&tags=%23x+y"
+    );
+
+    // 结尾的斜杠不会产生空的路径段
+    let url = endpoint::url("http://localhost:8080/", &CategoryList).unwrap();
+    assert_eq!(url.as_str(), "http://localhost:8080/categories");
+}
+
+#[test]
+fn bodies() {
+    let body = serde_json::to_value(SetVersion {
+        uri: "hello",
+        version: "v1",
+    })
+    .unwrap();
+    assert_eq!(body, serde_json::json!({ "version": "v1" }));
+
+    assert_eq!(DeleteArticle::METHOD, Method::DELETE);
+}
+
+#[test]
+fn invalid_base() {
+    assert!(matches!(
+        endpoint::url("blog.example.com", &CategoryList),
+        Err(Error::InvalidEndpoint(_))
+    ));
+}
@@ -0,0 +1,70 @@
+use blc::api::response::{Response, ResponseCode};
+use blc::settings::Settings;
+use blc::{Error, ErrorKind};
+
+fn error(envelope: &str) -> Error {
+    let resp = serde_json::from_str::<Response<()>>(envelope).unwrap();
+    Error::from(resp)
+}
+
+#[test]
+fn known_codes() {
+    let e = error(r#"{"code": "RESOURCE_NOT_EXIST", "data": null}"#);
+    assert!(matches!(
+        &e,
+        Error::ResponseError {
+            code: ResponseCode::ResourceNotExist,
+            ..
+        }
+    ));
+    assert_eq!(
+        e.to_string(),
+        "The requested resource does not exist. (RESOURCE_NOT_EXIST)"
+    );
+    assert_eq!(e.kind(), ErrorKind::NotFound);
+    assert_eq!(e.exit_code(), 4);
+
+    let e = error(r#"{"code": "PWD_ERROR", "data": null}"#);
+    assert_eq!(e.kind(), ErrorKind::Auth);
+}
+
+#[test]
+fn unknown_code_with_message() {
+    let e = error(r#"{"code": "RATE_LIMITED", "data": null, "message": "Slow down."}"#);
+    assert!(matches!(
+        &e,
+        Error::ResponseError { code: ResponseCode::Unknown(code), .. } if code == "RATE_LIMITED"
+    ));
+    assert_eq!(e.to_string(), "Slow down. (RATE_LIMITED)");
+    assert_eq!(e.kind(), ErrorKind::Server);
+
+    let e = error(r#"{"code": "RATE_LIMITED", "data": null}"#);
+    assert_eq!(
+        e.to_string(),
+        "The server answered with an unknown code RATE_LIMITED. (RATE_LIMITED)"
+    );
+}
+
+#[test]
+fn local_errors() {
+    assert_eq!(Error::InvalidQuery(String::new()).exit_code(), 2);
+    assert_eq!(Error::UnAuth.exit_code(), 3);
+    assert_eq!(
+        Error::IO(std::io::Error::other("disk full")).kind(),
+        ErrorKind::Io
+    );
+}
+
+#[test]
+fn config_keys() {
+    let e = Settings::get("page_size").unwrap_err();
+    assert!(matches!(&e, Error::UnknownConfigKey(key) if key == "page_size"));
+    assert_eq!(e.to_string(), "Unknown configuration key: page_size");
+    assert_eq!(e.exit_code(), 2);
+
+    // 已知但未设置的键不是未知的键
+    let e = Settings::get("templates.blc-test-unset").unwrap_err();
+    assert!(matches!(&e, Error::UnsetConfigKey(key) if key == "templates.blc-test-unset"));
+    assert_eq!(e.to_string(), "`templates.blc-test-unset` is not set.");
+    assert_eq!(e.exit_code(), 2);
+}
@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::PathBuf;
+
+use blc::api::fake::MemoryBackend;
+use blc::api::response::ResponseCode;
+use blc::api::BlogBackend;
+use blc::Error;
+
+fn response_code<T>(result: blc::Result<T>) -> ResponseCode {
+    match result {
+        Err(Error::ResponseError { code, .. }) => code,
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("expected an error response"),
+    }
+}
+
+fn markdown(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("blc-memory-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn requests_need_an_accepted_token() {
+    let blog = MemoryBackend::new("secret");
+    let anonymous = blog.with_token(None);
+
+    assert_eq!(
+        response_code(anonymous.get_category_list().await),
+        ResponseCode::Unauthorised
+    );
+    assert_eq!(
+        response_code(anonymous.login("wrong").await),
+        ResponseCode::PwdError
+    );
+
+    let session = anonymous.login("secret").await.unwrap();
+    assert_eq!(anonymous.token(), Some(session.token));
+    anonymous.verify_token().await.unwrap();
+
+    anonymous.revoke_token(false).await.unwrap();
+    assert_eq!(
+        response_code(anonymous.verify_token().await),
+        ResponseCode::Unauthorised
+    );
+    blog.verify_token().await.unwrap();
+
+    blog.revoke_token(true).await.unwrap();
+    assert_eq!(
+        response_code(blog.verify_token().await),
+        ResponseCode::Unauthorised
+    );
+}
+
+#[tokio::test]
+async fn articles_need_an_existing_category() {
+    let blog = MemoryBackend::new("secret");
+
+    assert_eq!(
+        response_code(blog.initializetion_article("hello", "notes").await),
+        ResponseCode::ResourceNotExist
+    );
+
+    blog.create_category("notes", "Notes").await.unwrap();
+    blog.initializetion_article("hello", "notes").await.unwrap();
+    assert_eq!(
+        response_code(blog.initializetion_article("hello", "notes").await),
+        ResponseCode::InvalidParameters
+    );
+
+    let resp = blog.get_category("notes").await.unwrap();
+    assert_eq!(resp.data().as_ref().unwrap().article_count, 1);
+    assert_eq!(
+        response_code(blog.delete_category("notes").await),
+        ResponseCode::InvalidAction
+    );
+}
+
+#[tokio::test]
+async fn versions_and_visibility() {
+    let blog = MemoryBackend::new("secret");
+    blog.create_category("notes", "Notes").await.unwrap();
+    blog.initializetion_article("hello", "notes").await.unwrap();
+
+    // 没有版本的文章不能公开
+    assert_eq!(
+        response_code(blog.set_article_visibility("hello", true).await),
+        ResponseCode::InvalidAction
+    );
+
+    let first = markdown("first.md", "# Hello\n\nworld\n");
+    let second = markdown("second.md", "no heading\n");
+    blog.upload_new_version("hello", &first).await.unwrap();
+    blog.upload_new_version("hello", &second).await.unwrap();
+    assert_eq!(
+        response_code(blog.upload_new_version("missing", &first).await),
+        ResponseCode::ResourceNotExist
+    );
+
+    let resp = blog.get_article_version_list("hello").await.unwrap();
+    let versions = resp.data().as_ref().unwrap();
+    assert_eq!(versions.count, 2);
+    assert_eq!(versions.items[0].title, "Hello");
+    assert_eq!(versions.items[1].title, "second");
+
+    let first_version = versions.items[0].version.clone();
+    let second_version = versions.items[1].version.clone();
+
+    // 当前版本不能删除
+    assert_eq!(
+        response_code(blog.delete_article_version("hello", &second_version).await),
+        ResponseCode::InvalidAction
+    );
+    blog.set_article_version("hello", &first_version)
+        .await
+        .unwrap();
+    blog.delete_article_version("hello", &second_version)
+        .await
+        .unwrap();
+    assert_eq!(
+        response_code(blog.set_article_version("hello", &second_version).await),
+        ResponseCode::ResourceNotExist
+    );
+
+    blog.set_article_visibility("hello", true).await.unwrap();
+    let resp = blog.get_article_list(1, None, None, None).await.unwrap();
+    let article = &resp.data().as_ref().unwrap().items[0];
+    assert!(article.visibility);
+    assert_eq!(article.version, first_version);
+}
+
+#[tokio::test]
+async fn article_list_filters_and_pages() {
+    let blog = MemoryBackend::new("secret");
+    blog.create_category("notes", "Notes").await.unwrap();
+    blog.create_category("posts", "Posts").await.unwrap();
+    for uri in ["a", "b", "c"] {
+        blog.initializetion_article(uri, "notes").await.unwrap();
+    }
+    blog.initializetion_article("d", "posts").await.unwrap();
+    blog.set_article_tags("a", "rust, cli").await.unwrap();
+    blog.set_article_tags("b", "rust").await.unwrap();
+
+    let resp = blog.get_article_list(1, Some(2), None, None).await.unwrap();
+    let articles = resp.data().as_ref().unwrap();
+    assert_eq!(articles.count, 4);
+    assert_eq!(articles.items[0].uri, "d");
+    assert!(articles.next && !articles.prev);
+
+    let resp = blog.get_article_list(2, Some(2), None, None).await.unwrap();
+    let articles = resp.data().as_ref().unwrap();
+    assert!(!articles.next && articles.prev);
+
+    let resp = blog
+        .get_article_list(1, None, Some("notes"), Some("rust,cli"))
+        .await
+        .unwrap();
+    let uris: Vec<_> = resp
+        .data()
+        .as_ref()
+        .unwrap()
+        .items
+        .iter()
+        .map(|a| &a.uri)
+        .collect();
+    assert_eq!(uris, ["a"]);
+
+    assert_eq!(
+        response_code(blog.get_article_list(0, None, None, None).await),
+        ResponseCode::InvalidParameters
+    );
+}
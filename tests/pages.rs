@@ -0,0 +1,41 @@
+use blc::api::fake::MemoryBackend;
+use blc::api::BlogBackend;
+
+async fn blog(articles: usize) -> MemoryBackend {
+    let blog = MemoryBackend::new("secret");
+    blog.create_category("notes", "Notes").await.unwrap();
+    for i in 0..articles {
+        blog.initializetion_article(&format!("post-{i}"), "notes")
+            .await
+            .unwrap();
+    }
+    blog
+}
+
+#[tokio::test]
+async fn follows_next_until_exhausted() {
+    let blog = blog(7).await;
+
+    let mut pages = blog.article_pages(Some(3), None, None);
+    let mut sizes = Vec::new();
+    while let Some(articles) = pages.next().await.unwrap() {
+        sizes.push(articles.items.len());
+    }
+
+    assert_eq!(sizes, [3, 3, 1]);
+    assert!(!pages.truncated());
+}
+
+#[tokio::test]
+async fn stops_at_max() {
+    let blog = blog(7).await;
+
+    let mut pages = blog.article_pages(Some(3), None, None).max(4);
+    let mut uris = Vec::new();
+    while let Some(articles) = pages.next().await.unwrap() {
+        uris.extend(articles.items.into_iter().map(|article| article.uri));
+    }
+
+    assert_eq!(uris, ["post-6", "post-5", "post-4", "post-3"]);
+    assert!(pages.truncated());
+}
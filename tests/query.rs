@@ -0,0 +1,124 @@
+use blc::api::response::{ArticleMetadata, Category};
+use blc::query::Query;
+use blc::Error;
+
+fn article(
+    uri: &str,
+    category: &str,
+    tags: &[&str],
+    visible: bool,
+    created: &str,
+) -> ArticleMetadata {
+    ArticleMetadata {
+        uri: uri.to_string(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        created_at: chrono::NaiveDate::parse_from_str(created, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis(),
+        version: "00000001".to_string(),
+        visibility: visible,
+        category: Category {
+            slug: category.to_string(),
+            name: category.to_string(),
+        },
+    }
+}
+
+fn matching(query: &str, articles: &[ArticleMetadata]) -> Vec<String> {
+    let query = Query::parse(query).unwrap();
+    articles
+        .iter()
+        .filter(|article| query.matches(article))
+        .map(|article| article.uri.clone())
+        .collect()
+}
+
+#[test]
+fn conditions() {
+    let articles = [
+        article("tokio", "rust", &["async"], false, "2024-03-01"),
+        article("axum", "rust", &["async", "web"], true, "2024-05-01"),
+        article("old", "rust", &["async"], false, "2023-06-01"),
+        article("django", "python", &["web"], false, "2024-02-01"),
+    ];
+
+    assert_eq!(
+        matching(
+            "category=rust and tag:async and not visible and created>2024-01-01",
+            &articles
+        ),
+        ["tokio"]
+    );
+    assert_eq!(
+        matching("tag:web and (visible or category != rust)", &articles),
+        ["axum", "django"]
+    );
+    assert_eq!(
+        matching("uri ~ o and tag != web", &articles),
+        ["tokio", "old"]
+    );
+    assert_eq!(
+        matching(
+            "created <= '2024-02-01 12:00:00' OR uri = \"axum\"",
+            &articles
+        ),
+        ["axum", "old", "django"]
+    );
+}
+
+#[test]
+fn timestamps() {
+    let articles = [
+        article("tokio", "rust", &["async"], false, "2024-03-01"),
+        article("axum", "rust", &["async", "web"], true, "2024-05-01"),
+        article("old", "rust", &["async"], false, "2023-06-01"),
+    ];
+
+    assert_eq!(
+        matching("created>2024-03-01T10:00:00", &articles),
+        ["tokio", "axum"]
+    );
+    // 12:00+08:00 即 UTC 04:00, 早于文章的创建时间
+    assert_eq!(
+        matching("created<2024-03-01T12:00:00+08:00", &articles),
+        ["old"]
+    );
+    assert_eq!(
+        matching("(created>=2024-05-01T12:00:00Z) or uri=old", &articles),
+        ["axum", "old"]
+    );
+}
+
+#[test]
+fn server_side_conditions() {
+    let query = Query::parse("category=rust and tag:async and (tag:web or visible)").unwrap();
+    assert_eq!(query.category(), Some("rust"));
+    assert_eq!(query.tags(), ["async"]);
+
+    let query = Query::parse("category=rust or tag:async").unwrap();
+    assert_eq!(query.category(), None);
+    assert!(query.tags().is_empty());
+}
+
+#[test]
+fn invalid_expressions() {
+    for source in [
+        "",
+        "category",
+        "author=me",
+        "tag:",
+        "(visible",
+        "visible visible",
+        "created>yesterday",
+        "created~2024",
+        "uri = 'unclosed",
+    ] {
+        assert!(
+            matches!(Query::parse(source), Err(Error::InvalidQuery(_))),
+            "{source}"
+        );
+    }
+}
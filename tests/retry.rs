@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use blc::api::RetryPolicy;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+
+#[test]
+fn backoff() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(500),
+        ..Default::default()
+    };
+
+    for _ in 0..20 {
+        let first = policy.backoff(0);
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+        let second = policy.backoff(1);
+        assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+        assert!(policy.backoff(10) <= Duration::from_millis(500));
+    }
+    assert!(policy.backoff(u32::MAX) <= Duration::from_millis(500));
+
+    assert!(policy.allows(&Method::PUT));
+    assert!(!policy.allows(&Method::POST));
+    assert!(RetryPolicy {
+        retry_post: true,
+        ..Default::default()
+    }
+    .allows(&Method::POST));
+}
+
+#[test]
+fn retry_after() {
+    let policy = RetryPolicy::default();
+    let mut headers = HeaderMap::new();
+
+    headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+    assert_eq!(
+        policy.retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+        Some(Duration::from_secs(3))
+    );
+    assert_eq!(policy.retry_after(StatusCode::BAD_GATEWAY, &headers), None);
+
+    headers.insert(RETRY_AFTER, HeaderValue::from_static("86400"));
+    assert_eq!(
+        policy.retry_after(StatusCode::SERVICE_UNAVAILABLE, &headers),
+        Some(Duration::from_secs(30))
+    );
+
+    // 已经过去的日期不需要等待
+    headers.insert(
+        RETRY_AFTER,
+        HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+    );
+    assert_eq!(
+        policy.retry_after(StatusCode::SERVICE_UNAVAILABLE, &headers),
+        Some(Duration::ZERO)
+    );
+}
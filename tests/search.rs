@@ -0,0 +1,139 @@
+use std::fs;
+
+use blc::api::fake::MemoryBackend;
+use blc::api::BlogBackend;
+use blc::output::{OutputFormat, Printer};
+use blc::search::{self, Search, VersionCache};
+use blc::settings::Settings;
+use blc::{commands, config, Cli};
+use clap::Parser;
+
+async fn blog(dir: &std::path::Path) -> MemoryBackend {
+    let blog = MemoryBackend::new("secret");
+    blog.create_category("notes", "Notes").await.unwrap();
+
+    for (uri, content) in [
+        ("tokio", "# Async runtimes\n"),
+        ("axum", "# Web services\n"),
+        ("async-notes", "# Scratch\n"),
+    ] {
+        blog.initializetion_article(uri, "notes").await.unwrap();
+        let path = dir.join(format!("{uri}.md"));
+        fs::write(&path, content).unwrap();
+        blog.upload_new_version(uri, &path).await.unwrap();
+    }
+    blog.set_article_tags("axum", "web, async").await.unwrap();
+    blog
+}
+
+#[tokio::test]
+async fn ranks_and_highlights() {
+    colored::control::set_override(false);
+    let dir = tempfile::tempdir().unwrap();
+    let blog = blog(dir.path()).await;
+
+    let articles = blog.get_article_list(1, None, None, None).await.unwrap();
+    let articles = articles.into_data().unwrap().items;
+    let versions = search::versions(&blog, &articles, None).await.unwrap();
+
+    let results = Search::new("ASYNC").rank(articles, versions);
+    let uris = results
+        .items
+        .iter()
+        .map(|hit| hit.uri.as_str())
+        .collect::<Vec<_>>();
+    // 标题命中的权重最高, 其次是 URI 和标签
+    assert_eq!(uris, ["tokio", "async-notes", "axum"]);
+    assert!(results.items[0]
+        .to_string()
+        .contains("title  Async runtimes"));
+}
+
+#[tokio::test]
+async fn caches_version_lists() {
+    colored::control::set_override(false);
+    let dir = tempfile::tempdir().unwrap();
+    let blog = blog(dir.path()).await;
+    let settings = Settings {
+        cache_dir: Some(dir.path().join("cache")),
+        ..Default::default()
+    };
+
+    let search = |args: &'static [&'static str]| {
+        let cli = Cli::try_parse_from(std::iter::once("blc").chain(args.iter().copied())).unwrap();
+        let blog = &blog;
+        let settings = &settings;
+        async move {
+            let mut out = Vec::new();
+            let mut printer = Printer::new(OutputFormat::Text, &mut out);
+            let info = commands::run(
+                blog,
+                &cli.action,
+                settings,
+                config::DEFAULT_PROFILE,
+                &mut printer,
+            )
+            .await
+            .unwrap();
+            (info, String::from_utf8(out).unwrap())
+        }
+    };
+
+    let (info, out) = search(&["search", "web"]).await;
+    assert_eq!(info.as_deref(), Some("Search done. Total 1 matches."));
+    assert!(out.starts_with("axum [notes] web, async\n"), "{out}");
+
+    // 当前版本未变时直接使用缓存
+    let cache = VersionCache::new(dir.path().join("cache").join("default"));
+    let axum = blog
+        .get_article_list(1, None, None, Some("web"))
+        .await
+        .unwrap();
+    let axum = &axum.data().as_ref().unwrap().items[0];
+    let mut versions = cache.get(axum).unwrap();
+    versions.items[0].title = "Cached title".to_string();
+    cache.put(axum, versions).unwrap();
+
+    let (_, out) = search(&["search", "cached"]).await;
+    assert!(out.contains("Cached title"), "{out}");
+
+    let (info, out) = search(&["search", "cached", "--refresh"]).await;
+    assert_eq!(info.as_deref(), Some("Search done. Total 0 matches."));
+    assert!(out.is_empty(), "{out}");
+
+    // 删除非当前版本不改变当前版本, 缓存仍需失效
+    let path = dir.path().join("axum.md");
+    fs::write(&path, "# Web services, revised\n").unwrap();
+    let upload = format!("blc upload axum {}", path.display());
+    run(&blog, &settings, &upload).await;
+    let old = blog.get_article_version_list("axum").await.unwrap();
+    let old = old.into_data().unwrap().items[0].version.clone();
+    search(&["search", "web"]).await;
+
+    let axum = blog
+        .get_article_list(1, None, None, Some("web"))
+        .await
+        .unwrap();
+    let axum = &axum.data().as_ref().unwrap().items[0];
+    let mut versions = cache.get(axum).unwrap();
+    versions.items[0].title = "Cached title".to_string();
+    cache.put(axum, versions).unwrap();
+    run(&blog, &settings, &format!("blc rm axum {old}")).await;
+
+    let (info, _) = search(&["search", "cached"]).await;
+    assert_eq!(info.as_deref(), Some("Search done. Total 0 matches."));
+}
+
+async fn run(blog: &MemoryBackend, settings: &Settings, command: &str) {
+    let cli = Cli::try_parse_from(command.split_whitespace()).unwrap();
+    let mut printer = Printer::new(OutputFormat::Text, Vec::new());
+    commands::run(
+        blog,
+        &cli.action,
+        settings,
+        config::DEFAULT_PROFILE,
+        &mut printer,
+    )
+    .await
+    .unwrap();
+}
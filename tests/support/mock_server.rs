@@ -0,0 +1,539 @@
+//! A stand-in BlogLite server on a local port, backed by [`MemoryBackend`].
+//!
+//! It speaks the same `{code, data}` envelope as the real server and can inject faults
+//! (latency, stalled bodies, HTTP 500 and 503, HTML error pages, malformed JSON) to exercise the error paths of the client.
+//! Every request is logged, so that tests can check the method and path the client used.
+
+// 测试和 examples/blc-mock-server.rs 共用, 各自只用到其中一部分
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Multipart, Path, Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, patch, post, put};
+use axum::{Json, Router};
+use blc::api::fake::MemoryBackend;
+use blc::api::{response, BlogBackend};
+use blc::Error;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::oneshot;
+
+/// How long the body of a response is held back by [`MockServer::stall_body_next`].
+pub const STALL: Duration = Duration::from_secs(2);
+
+/// A running mock server, stopped when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+struct ServerState {
+    blog: MemoryBackend,
+    uploads: tempfile::TempDir,
+    faults: Faults,
+
+    /// `METHOD /path?query` of every request, as sent.
+    requests: Mutex<Vec<String>>,
+}
+
+#[derive(Default)]
+struct Faults {
+    latency: Mutex<Duration>,
+    errors: AtomicUsize,
+    unavailable: AtomicUsize,
+    malformed: AtomicUsize,
+    page: Mutex<Option<StatusCode>>,
+    garbled_token: AtomicUsize,
+    stalled_body: AtomicUsize,
+}
+
+impl MockServer {
+    /// Start a server on a free local port, accepting `password` for login.
+    pub fn start(password: &str) -> MockServer {
+        MockServer::bind("127.0.0.1:0", password)
+    }
+
+    /// Start a server on the given address, e.g. `127.0.0.1:8080`.
+    pub fn bind(addr: &str, password: &str) -> MockServer {
+        let listener = std::net::TcpListener::bind(addr).expect("bind mock server");
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let state = Arc::new(ServerState {
+            blog: MemoryBackend::new(password),
+            uploads: tempfile::tempdir().unwrap(),
+            faults: Faults::default(),
+            requests: Mutex::default(),
+        });
+        let (shutdown, stopped) = oneshot::channel::<()>();
+
+        let app = router(Arc::clone(&state));
+        // 单独的线程和运行时, 同步测试和 #[tokio::test] 都能使用
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(async {
+                        let _ = stopped.await;
+                    })
+                    .await
+                    .unwrap();
+            });
+        });
+
+        MockServer {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Base URL to pass as `--api`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A logged in handle to the blog behind the server, for preparing or inspecting state.
+    pub fn blog(&self) -> &MemoryBackend {
+        &self.state.blog
+    }
+
+    /// Delay every response by `latency`.
+    pub fn set_latency(&self, latency: Duration) {
+        *self.state.faults.latency.lock().unwrap() = latency;
+    }
+
+    /// Answer the next `count` requests with HTTP 500.
+    pub fn fail_next(&self, count: usize) {
+        self.state.faults.errors.store(count, Ordering::SeqCst);
+    }
+
+    /// Answer the next `count` requests with HTTP 503 and `Retry-After: 0`.
+    pub fn unavailable_next(&self, count: usize) {
+        self.state.faults.unavailable.store(count, Ordering::SeqCst);
+    }
+
+    /// Answer the next request with an HTML page and `status`, like a proxy in front of the server.
+    pub fn html_next(&self, status: u16) {
+        *self.state.faults.page.lock().unwrap() = Some(StatusCode::from_u16(status).unwrap());
+    }
+
+    /// Send a token that is not valid ASCII with the next `count` successful logins.
+    pub fn garble_token_next(&self, count: usize) {
+        self.state
+            .faults
+            .garbled_token
+            .store(count, Ordering::SeqCst);
+    }
+
+    /// Send the headers of the next `count` responses at once, but their body only after `STALL`.
+    pub fn stall_body_next(&self, count: usize) {
+        self.state
+            .faults
+            .stalled_body
+            .store(count, Ordering::SeqCst);
+    }
+
+    /// The requests received so far, e.g. `PUT /articles/hello/version`.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Answer the next `count` requests with a truncated JSON body.
+    pub fn malform_next(&self, count: usize) {
+        self.state.faults.malformed.store(count, Ordering::SeqCst);
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+type AppState = State<Arc<ServerState>>;
+
+fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/authentication", post(login).delete(revoke))
+        .route("/authentication/sessions", delete(revoke_all))
+        .route("/articles", post(init_article))
+        .route("/articles/{uri}", delete(delete_article))
+        .route("/articles/{uri}/versions", get(versions).post(upload))
+        .route("/articles/{uri}/versions/{version}", delete(delete_version))
+        .route("/articles/{uri}/visibility", patch(set_visibility))
+        .route("/articles/{uri}/category", patch(set_category))
+        .route("/articles/{uri}/tags", patch(set_tags))
+        .route("/articles/{uri}/version", put(set_version))
+        .route("/author/articles", get(article_list))
+        .route("/categories", get(category_list).post(create_category))
+        .route(
+            "/categories/{slug}",
+            get(category).patch(rename_category).delete(delete_category),
+        )
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            inject_faults,
+        ))
+        .with_state(state)
+}
+
+async fn inject_faults(State(state): AppState, req: Request, next: Next) -> Response {
+    state
+        .requests
+        .lock()
+        .unwrap()
+        .push(format!("{} {}", req.method(), req.uri()));
+
+    let faults = &state.faults;
+
+    let latency = *faults.latency.lock().unwrap();
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+
+    if take(&faults.errors) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+    }
+    if take(&faults.unavailable) {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "0")],
+            "Service Unavailable",
+        )
+            .into_response();
+    }
+    if let Some(status) = faults.page.lock().unwrap().take() {
+        return (
+            status,
+            [(header::CONTENT_TYPE, "text/html")],
+            format!("<html><body><h1>{status}</h1><hr>nginx</body></html>"),
+        )
+            .into_response();
+    }
+    if take(&faults.malformed) {
+        return (
+            [(header::CONTENT_TYPE, "application/json")],
+            r#"{"code": "OK", "data": {"#,
+        )
+            .into_response();
+    }
+
+    let resp = next.run(req).await;
+    if !take(&faults.stalled_body) {
+        return resp;
+    }
+    let (parts, body) = resp.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    let stalled = futures::stream::once(async move {
+        tokio::time::sleep(STALL).await;
+        Ok::<_, std::convert::Infallible>(bytes)
+    });
+    Response::from_parts(parts, axum::body::Body::from_stream(stalled))
+}
+
+/// Consume one pending fault, if there is any left.
+fn take(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// A handle to the blog acting with the bearer token of the request.
+fn client(state: &ServerState, headers: &HeaderMap) -> MemoryBackend {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    state.blog.with_token(token)
+}
+
+/// Wrap the result in the `{code, data}` envelope of BlogLite.
+fn envelope<D: serde::Serialize>(result: blc::Result<response::Response<D>>) -> Response {
+    match result {
+        Ok(resp) => Json(resp).into_response(),
+        Err(Error::ResponseError { code, .. }) => {
+            Json(json!({ "code": code, "data": null })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct Password {
+    password: String,
+}
+
+async fn login(State(state): AppState, Json(body): Json<Password>) -> Response {
+    let anonymous = state.blog.with_token(None);
+    match anonymous.login(&body.password).await {
+        Ok(session) => {
+            let mut resp = Json(json!({ "code": "OK", "data": null })).into_response();
+            let token = if take(&state.faults.garbled_token) {
+                HeaderValue::from_bytes("令牌".as_bytes()).unwrap()
+            } else {
+                HeaderValue::from_str(&session.token).unwrap()
+            };
+            resp.headers_mut().insert("X-Auth-Token", token);
+            if let Some(lifetime) = session.lifetime {
+                resp.headers_mut()
+                    .insert("X-Auth-Token-Expires-In", lifetime.num_seconds().into());
+            }
+            resp
+        }
+        Err(e) => envelope(Err::<response::Response<()>, _>(e)),
+    }
+}
+
+async fn revoke(State(state): AppState, headers: HeaderMap) -> Response {
+    envelope(client(&state, &headers).revoke_token(false).await)
+}
+
+async fn revoke_all(State(state): AppState, headers: HeaderMap) -> Response {
+    envelope(client(&state, &headers).revoke_token(true).await)
+}
+
+#[derive(Deserialize)]
+struct NewArticle {
+    uri: String,
+    category: String,
+}
+
+async fn init_article(
+    State(state): AppState,
+    headers: HeaderMap,
+    Json(body): Json<NewArticle>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .initializetion_article(&body.uri, &body.category)
+            .await,
+    )
+}
+
+async fn delete_article(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(uri): Path<String>,
+) -> Response {
+    envelope(client(&state, &headers).delete_article(&uri).await)
+}
+
+async fn versions(State(state): AppState, headers: HeaderMap, Path(uri): Path<String>) -> Response {
+    envelope(
+        client(&state, &headers)
+            .get_article_version_list(&uri)
+            .await,
+    )
+}
+
+async fn upload(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(uri): Path<String>,
+    mut multipart: Multipart,
+) -> Response {
+    let mut saved = None;
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if field.name() != Some("content") {
+            continue;
+        }
+
+        // 保留原文件名, 内存后端用它作为没有标题时的版本标题
+        let name = field.file_name().unwrap_or("default.md").to_string();
+        let Ok(bytes) = field.bytes().await else {
+            break;
+        };
+        let path = state.uploads.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        saved = Some(path);
+    }
+
+    let Some(path) = saved else {
+        return Json(json!({ "code": "INVALID_PARAMETERS", "data": null })).into_response();
+    };
+    envelope(
+        client(&state, &headers)
+            .upload_new_version(&uri, &path)
+            .await,
+    )
+}
+
+async fn delete_version(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path((uri, version)): Path<(String, String)>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .delete_article_version(&uri, &version)
+            .await,
+    )
+}
+
+#[derive(Deserialize)]
+struct Visibility {
+    visibility: bool,
+}
+
+async fn set_visibility(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(uri): Path<String>,
+    Json(body): Json<Visibility>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .set_article_visibility(&uri, body.visibility)
+            .await,
+    )
+}
+
+#[derive(Deserialize)]
+struct ArticleCategory {
+    category: String,
+}
+
+async fn set_category(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(uri): Path<String>,
+    Json(body): Json<ArticleCategory>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .set_article_category(&uri, &body.category)
+            .await,
+    )
+}
+
+#[derive(Deserialize)]
+struct Tags {
+    tags: Vec<String>,
+}
+
+async fn set_tags(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(uri): Path<String>,
+    Json(body): Json<Tags>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .set_article_tags(&uri, &body.tags.join(","))
+            .await,
+    )
+}
+
+#[derive(Deserialize)]
+struct CurrentVersion {
+    version: String,
+}
+
+async fn set_version(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(uri): Path<String>,
+    Json(body): Json<CurrentVersion>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .set_article_version(&uri, &body.version)
+            .await,
+    )
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    page: u32,
+    limit: Option<u32>,
+    category: Option<String>,
+    tags: Option<String>,
+}
+
+async fn article_list(
+    State(state): AppState,
+    headers: HeaderMap,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .get_article_list(
+                query.page,
+                query.limit,
+                query.category.as_deref(),
+                query.tags.as_deref(),
+            )
+            .await,
+    )
+}
+
+async fn category_list(State(state): AppState, headers: HeaderMap) -> Response {
+    envelope(client(&state, &headers).get_category_list().await)
+}
+
+#[derive(Deserialize)]
+struct NewCategory {
+    slug: String,
+    name: String,
+}
+
+async fn create_category(
+    State(state): AppState,
+    headers: HeaderMap,
+    Json(body): Json<NewCategory>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .create_category(&body.slug, &body.name)
+            .await,
+    )
+}
+
+async fn category(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+) -> Response {
+    envelope(client(&state, &headers).get_category(&slug).await)
+}
+
+#[derive(Deserialize)]
+struct CategoryName {
+    name: String,
+}
+
+async fn rename_category(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+    Json(body): Json<CategoryName>,
+) -> Response {
+    envelope(
+        client(&state, &headers)
+            .rename_category(&slug, &body.name)
+            .await,
+    )
+}
+
+async fn delete_category(
+    State(state): AppState,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+) -> Response {
+    envelope(client(&state, &headers).delete_category(&slug).await)
+}
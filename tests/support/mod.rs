@@ -0,0 +1,73 @@
+#![allow(dead_code)]
+
+pub mod mock_server;
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use tempfile::TempDir;
+
+/// Runs the compiled `blc` binary with its own home directory and no `BLC_*` variables.
+pub struct Blc {
+    home: TempDir,
+    api: String,
+}
+
+impl Blc {
+    pub fn new(api: &str) -> Blc {
+        Blc {
+            home: tempfile::tempdir().unwrap(),
+            api: api.to_string(),
+        }
+    }
+
+    pub fn home(&self) -> &Path {
+        self.home.path()
+    }
+
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_blc"));
+        command
+            .env_clear()
+            .env("HOME", self.home())
+            .env("BLC_API", &self.api)
+            .current_dir(self.home());
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command().args(args).output().unwrap()
+    }
+
+    /// Run with `input` on standard input, e.g. for `login --password-stdin`.
+    pub fn run_with_input(&self, args: &[&str], input: &str) -> Output {
+        let mut child = self
+            .command()
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    pub fn login(&self, password: &str) -> Output {
+        self.run_with_input(&["login", "--password-stdin"], &format!("{password}\n"))
+    }
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
@@ -0,0 +1,86 @@
+use blc::api::response::{ArticleMetadata, Category};
+use blc::table::{self, ARTICLE_COLUMNS};
+use blc::{Error, TableArgs};
+
+fn article(uri: &str, category: &str, tags: &[&str], created_at: i64) -> ArticleMetadata {
+    ArticleMetadata {
+        uri: uri.to_string(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        created_at,
+        version: "00000001".to_string(),
+        visibility: false,
+        category: Category {
+            slug: category.to_string(),
+            name: category.to_string(),
+        },
+    }
+}
+
+fn columns(names: &str) -> TableArgs {
+    TableArgs {
+        columns: Some(names.split(',').map(str::to_string).collect()),
+        no_header: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn aligned_columns() {
+    colored::control::set_override(false);
+    let rows = [
+        article("hello", "notes", &["rust", "cli"], 0),
+        article("a-longer-uri", "posts", &[], 0),
+    ];
+    let args = TableArgs {
+        columns: Some(vec!["uri".into(), "category".into(), "tags".into()]),
+        ..Default::default()
+    };
+
+    let text = table::render(ARTICLE_COLUMNS, &rows, &args, None).unwrap();
+    assert_eq!(
+        text,
+        "URI           CATEGORY  TAGS\n\
+         hello         notes     rust, cli\n\
+         a-longer-uri  posts"
+    );
+}
+
+#[test]
+fn sort_and_reverse() {
+    let mut rows = vec![
+        article("b", "notes", &[], 3_000),
+        article("c", "art", &[], 1_000),
+        article("a", "posts", &[], 2_000),
+    ];
+    let uris = |rows: &[ArticleMetadata]| rows.iter().map(|a| a.uri.clone()).collect::<Vec<_>>();
+
+    let mut args = columns("uri");
+    args.sort = Some("created".into());
+    table::sort(ARTICLE_COLUMNS, &mut rows, &args).unwrap();
+    assert_eq!(uris(&rows), ["c", "a", "b"]);
+
+    args.sort = Some("category".into());
+    args.reverse = true;
+    table::sort(ARTICLE_COLUMNS, &mut rows, &args).unwrap();
+    assert_eq!(uris(&rows), ["a", "b", "c"]);
+}
+
+#[test]
+fn truncate_to_width() {
+    let rows = [article(
+        "a-very-long-article-uri",
+        "notes",
+        &["一二三四五六七八"],
+        0,
+    )];
+
+    let text = table::render(ARTICLE_COLUMNS, &rows, &columns("uri,tags"), Some(30)).unwrap();
+    assert_eq!(text, "a-very-long-a…  一二三四五六…");
+    assert!(unicode_width::UnicodeWidthStr::width(text.as_str()) <= 30);
+}
+
+#[test]
+fn unknown_column() {
+    let result = table::render(ARTICLE_COLUMNS, &[], &columns("uri,title"), None);
+    assert!(matches!(result, Err(Error::UnknownColumn { column, .. }) if column == "title"));
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use blc::api::response::{ArticleMetadata, Category, Version};
+use blc::template::Template;
+use blc::Error;
+
+fn article() -> ArticleMetadata {
+    ArticleMetadata {
+        uri: "hello".to_string(),
+        tags: vec!["rust".to_string(), "cli".to_string()],
+        created_at: 1_700_000_000_000,
+        version: "00000001".to_string(),
+        visibility: true,
+        category: Category {
+            slug: "notes".to_string(),
+            name: "Notes".to_string(),
+        },
+    }
+}
+
+#[test]
+fn fields_and_filters() {
+    let template = Template::parse(
+        r#"{uri}\t{category.slug|upper}\t{tags|join(", ")}\t{created|date("%Y-%m-%d")}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        template.render(&article()).unwrap(),
+        "hello\tNOTES\trust, cli\t2023-11-14"
+    );
+
+    let template = Template::parse("{{{uri}}} {visible}").unwrap();
+    assert_eq!(template.render(&article()).unwrap(), "{hello} true");
+}
+
+#[test]
+fn default_value() {
+    let version = Version {
+        version: "00000001".to_string(),
+        note: String::new(),
+        title: "Hello".to_string(),
+        created_at: 0,
+    };
+
+    let template =
+        Template::parse(r#"{title}: {note|default("no note")} {missing|default}"#).unwrap();
+    assert_eq!(template.render(&version).unwrap(), "Hello: no note ");
+}
+
+#[test]
+fn named_templates() {
+    let templates = HashMap::from([("index".to_string(), "- [{uri}](/{uri})".to_string())]);
+
+    let template = Template::resolve("index", &templates).unwrap();
+    assert_eq!(template.render(&article()).unwrap(), "- [hello](/hello)");
+}
+
+#[test]
+fn invalid_templates() {
+    for source in ["{uri", "uri}", "{}", "{uri|reverse}", "{tags|join(,)}"] {
+        assert!(
+            matches!(Template::parse(source), Err(Error::InvalidTemplate(_))),
+            "{source}"
+        );
+    }
+
+    let template = Template::parse("{url}").unwrap();
+    assert!(matches!(
+        template.render(&article()),
+        Err(Error::InvalidTemplate(_))
+    ));
+}